@@ -1,5 +1,6 @@
 //! simple basic cli interface for lprun library
 extern crate clap;
+extern crate failure;
 extern crate lprun;
 
 use lprun::interface;
@@ -26,9 +27,21 @@ fn main() {
         }
     }
 
-    // processess the arguement matches.
+    // processess the arguement matches, exits with the same code as love if it was waited on.
     match interface::process(&app) {
-        Err(error) => { println!("{}",error); }
-        Ok(_) => { }
+        Err(error) => { 
+            eprintln!("{}",error);
+            // the library's errors keep what caused them
+            let fail : &dyn failure::Fail = &error;
+            for cause in fail.iter_causes() { eprintln!("  caused by: {}",cause); }
+            std::process::exit(1);
+        }
+        Ok(None) => { }
+        Ok(Some(status)) => {
+            if !status.success() {
+                // no code means it was killed by a signal
+                std::process::exit(status.code().unwrap_or(1));
+            }
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,50 @@
+//! linux releases can be AppImages, which are already the binary so
+//! they aren't extracted like the other archives.
+//! 
+//! systems without FUSE can't run AppImages directly, so setting
+//! `lprun.appimage.extract` unpacks it with `--appimage-extract` instead.
+
+use error::Error;
+use lpsettings;
+
+use std::path::{Path,PathBuf};
+use std::process::Command;
+
+pub fn is_appimage(file_name : &str) -> bool {
+    file_name.to_lowercase().ends_with(".appimage")
+}
+
+pub fn should_extract() -> bool {
+    //! if AppImages should be unpacked instead of run directly, the `lprun.appimage.extract` setting
+    
+    match lpsettings::get_value_or("lprun.appimage.extract",&false) {
+        lpsettings::Type::Switch(extract) => extract,
+        _ => false,
+    }
+}
+
+pub fn extract<P : AsRef<Path>, Q : AsRef<Path>>(appimage : P, destination : Q) -> Result<PathBuf,Error> {
+    //! unpacks the AppImage into the destination, returning the path to its `AppRun`
+    //! 
+    //! the AppImage needs to be executable already.
+    
+    let appimage = appimage.as_ref();
+    let image_name = appimage.display().to_string();
+
+    let status = Command::new(appimage)
+        .arg("--appimage-extract")
+        .current_dir(destination.as_ref())
+        .status()
+        .map_err(|error| Error::extract(&image_name, error))?;
+
+    if !status.success() {
+        return Err(Error::extract(&image_name, format_err!("--appimage-extract failed with {}",status)));
+    }
+
+    let app_run = destination.as_ref().join("squashfs-root").join("AppRun");
+    if app_run.exists() {
+        Ok(app_run)
+    } else {
+        Err(Error::extract(&image_name, format_err!("no AppRun found in the extracted AppImage")))
+    }
+}
@@ -1,18 +1,27 @@
-use platform_lp::Platform;
+use platform_lp::{PartialPlatform, Platform};
 use version_lp::Version;
 use lpsettings;
-use download_lp;
 use archive_lp;
 
-use failure::Error;
+use error::Error;
 
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use std::fs::{create_dir_all,remove_file,read_dir};
+use std::process::{Command,ExitStatus};
+use std::fs::{create_dir_all,remove_dir_all,read_dir,rename};
 
 use std::collections::HashSet;
 use structs::release::Release;
+use structs::options::{RunOptions, InstallOptions, Phase};
+use structs::manifest::InstallManifest;
 use repo;
+use love;
+use checksum;
+use download;
+use appimage;
+use macos;
+use offline;
+
+use smart_hash::traits::SmartHashSet;
 
 pub fn build_path(platform : &Platform, version : &Version) -> Result<PathBuf,Error> {
     //! generates the path to the binary, used for executing.
@@ -36,10 +45,21 @@ pub fn build_path(platform : &Platform, version : &Version) -> Result<PathBuf,Er
     //! }
     //! ```
 
-    let mut path = lpsettings::get_folder();
-    let binary_path = lpsettings::get_value_or("run.binaries-root",&"bin".to_string());
+    // installs know where their binary is
+    let install_path = get_install_folder(platform,version);
+    if let Some(manifest) = InstallManifest::load(&install_path) {
+        return Ok(install_path.join(manifest.executable));
+    }
 
-    path.push(binary_path.to_string());
+    // otherwise guesses, for installs from older versions of lprun
+    guess_path(&get_binaries_root(), platform, version)
+}
+
+fn guess_path(binaries_root : &Path, platform : &Platform, version : &Version) -> Result<PathBuf,Error> {
+    //! where the binary should be in the binaries root, based on how the 
+    //! platform's releases are laid out.
+    
+    let mut path = binaries_root.to_path_buf();
     path.push(platform.to_short_string());
 
     if platform == &Platform::Win32 || platform == &Platform::Win64 { 
@@ -48,93 +68,235 @@ pub fn build_path(platform : &Platform, version : &Version) -> Result<PathBuf,Er
     } else if platform == &Platform::Nix32 || platform == &Platform::Nix64 { 
         path.push(version.to_string());
         path.push("love"); 
-    } 
+    } else if *platform == PartialPlatform::Mac {
+        path.push(version.to_string());
+        // the bundle isn't always called love.app, so uses whatever was installed
+        match macos::find_executable(&path) {
+            Some(executable) => return Ok(executable),
+            None => path.push(macos::DEFAULT_EXECUTABLE),
+        }
+    } else {
+        return Err(Error::PlatformUnsupported { platform : platform.to_string() });
+    }
 
     Ok(path)
 }
 
 pub fn install(platform : &Platform, version : &Version) -> Result<PathBuf,Error> {
+    //! installs with the default options, see `install_with`
+    
+    install_with(platform, version, &InstallOptions::default())
+}
+
+pub fn install_with(platform : &Platform, version : &Version, options : &InstallOptions) -> Result<PathBuf,Error> {
     //! doesn't check if it already exists, you should do this before.
     //! 
     //! will install the desired version in the local repo stop. if the folder already exists
     //! then it assumes it is already installed and returns that path.
+    //! 
+    //! everything is downloaded and extracted into a staging folder first, and is only moved
+    //! into place once it all worked, so a failed install never looks like a finished one.
+    //! downloads that fail part way are kept, and are resumed by the next install.
+    //! if the folder exists but the binary isn't in it then it is treated as a broken install
+    //! (older versions of lprun installed directly into the folder) and is reinstalled.
+    //! 
+    //! fails when offline, saying what would have been downloaded, unless the
+    //! release is from a local folder.
+    //! 
+    //! the `options` can be used to get progress reports and to cancel the install.
     
     let install_exe = build_path(platform,version)?;
-    match install_exe.parent() {
-        None => Err(format_err!("Couldn't get the folder for the path to install: {}",
-            install_exe.display().to_string())),
-        Some(install_path) => {
-            if !install_path.exists() {
-                create_dir_all(install_path)?;
-
-                let link = repo::get_version_link(platform,version)?;
-                info!("Installing from '{}'",link);
-                let (download_file_name,_size) = download_lp::download(&link, install_path.display().to_string())?;
-                let download_path = {
-                    let mut path = PathBuf::from(install_path);
-                    path.push(download_file_name);
-                    path
-                };
-                let exe_path = archive_lp::extract_root_to(&download_path.display().to_string(), &install_path.display().to_string())?;
-
-                remove_file(download_path)?;
-
-                Ok(exe_path)
-            } else {
-                info!("Path '{}' already exists, assuming it was already installed.",
-                    install_path.display().to_string());
-                Ok(install_exe.clone())
+    let install_path = get_install_folder(platform,version);
+
+    if install_path.exists() && install_exe.exists() {
+        info!("Path '{}' already exists, assuming it was already installed.",
+            install_path.display().to_string());
+        return Ok(install_exe);
+    }
+
+    // checked before repairing anything, so a broken install isn't removed when it can't be replaced.
+    if offline::is_offline() {
+        let release = repo::get_release(platform,version)?;
+        if download::local_path(&release.link).is_none() {
+            return Err(Error::Offline { needed : format!("LOVE {} for {} from '{}'",version,platform,release.link) });
+        }
+    }
+
+    if install_path.exists() {
+        warn!("Path '{}' exists but LOVE isn't in it, removing the unfinished install.",
+            install_path.display().to_string());
+        remove_dir_all(&install_path)?;
+    }
+
+    let staging_path = get_staging_folder(platform,version);
+    if staging_path.exists() {
+        info!("Removing leftovers from a previous install in '{}'",staging_path.display().to_string());
+        remove_dir_all(&staging_path)?;
+    }
+    create_dir_all(&staging_path)?;
+
+    let download_folder = get_download_folder(platform,version);
+    create_dir_all(&download_folder)?;
+
+    let staged_exe = match install_to(platform,version,&staging_path,&download_folder,options) {
+        Ok(exe) => exe,
+        Err(error) => {
+            if let Err(cleanup_error) = remove_dir_all(&staging_path) {
+                error!("Failed to clean up '{}': {}",staging_path.display().to_string(),cleanup_error);
+            }
+            // a bad download can't be resumed, so starts over next time.
+            match error {
+                Error::Checksum { .. } | Error::Extract { .. } => {
+                    if let Err(cleanup_error) = remove_dir_all(&download_folder) {
+                        error!("Failed to clean up '{}': {}",download_folder.display().to_string(),cleanup_error);
+                    }
+                },
+                _ => (),
             }
+            return Err(error);
         }
+    };
+    remove_dir_all(&download_folder)?;
+
+    // everything worked, so moves it into place.
+    if let Some(parent) = install_path.parent() {
+        create_dir_all(parent)?;
+    }
+    rename(&staging_path,&install_path)?;
+
+    // the binary was found in the staging folder, so needs to point to where it is now
+    match staged_exe.strip_prefix(&staging_path) {
+        Ok(relative) => Ok(install_path.join(relative)),
+        Err(_) => Ok(install_exe),
     }
 }
 
-pub fn run<P:AsRef<Path>>(binary_path : P, package_path : Option<PathBuf>) -> Result<(),Error> {
+pub fn run<P:AsRef<Path>>(binary_path : P, package_path : Option<PathBuf>, options : &RunOptions) -> Result<Option<ExitStatus>,Error> {
     //! doesn't check if it exists, you should check before using this.
     //! 
     //! direct run function. will try and run the app and error if it can't it will not
     //! attempt to install the binary, use core::run instead if you want that functionality.
+    //! 
+    //! waits for LOVE to close and returns its exit status, unless `options.detach` is used, 
+    //! which returns as soon as LOVE is started (and so has no status).
     
     let path = PathBuf::from(binary_path.as_ref());
     
     let mut command = Command::new(&path);
     if let Some(package_path) = package_path { command.arg(package_path); }
+    command.args(&options.args);
+    for &(ref key, ref value) in &options.env { command.env(key,value); }
+
+    let mut child = match command.spawn() {
+        Err(error) => return Err(Error::Spawn { path : path.display().to_string(), cause : error }),
+        Ok(child) => child,
+    };
 
-    match command.spawn() {
-        Err(error) => Err(format_err!("{}",error)),
-        Ok(_child) => Ok(()) // maybe use this in the future?
+    if options.detach {
+        info!("Started LOVE with pid {}, not waiting for it",child.id());
+        return Ok(None);
     }
+
+    let status = match child.wait() {
+        Err(error) => return Err(Error::Spawn { path : path.display().to_string(), cause : error }),
+        Ok(status) => status,
+    };
+    info!("LOVE exited with {}",status);
+    Ok(Some(status))
+}
+
+pub fn uninstall(platform : &Platform, version : &Version) -> Result<PathBuf,Error> {
+    //! removes an installed version, returns the folder that was removed.
+    //! 
+    //! will error if the version isn't installed.
+    
+    let install_path = get_install_folder(platform,version);
+
+    if !install_path.exists() {
+        return Err(Error::NotInstalled { platform : platform.to_string(), version : version.to_string() });
+    }
+
+    info!("Removing '{}'",install_path.display().to_string());
+    remove_dir_all(&install_path)?;
+
+    Ok(install_path)
+}
+
+pub fn prune<P : AsRef<Path>>(keep : usize, projects : &[P]) -> Result<Vec<Release>,Error> {
+    //! removes old installed versions, returns the releases that were removed.
+    //! 
+    //! keeps the `keep` newest versions for each platform, and any version
+    //! that one of the `projects` requires.
+    
+    let mut required : Vec<Version> = Vec::new();
+    for project in projects {
+        match love::project::get_required_version(project.as_ref()) {
+            Ok(version) => required.push(version),
+            Err(_) => { 
+                info!("Couldn't determine the version for project '{}'",project.as_ref().display().to_string()); 
+            },
+        }
+    }
+
+    let installed = get_installed()?;
+    let mut removed : Vec<Release> = Vec::new();
+    
+    for platform_in_question in Platform::iterator() {
+        if let Some(mut release_set) = get_matching!(installed,platform == platform_in_question.clone()) {
+            release_set.sort();
+            release_set.reverse();
+            
+            for release in release_set.iter().skip(keep) {
+                if required.contains(&release.version) {
+                    info!("Keeping {}, required by a project",release);
+                } else {
+                    uninstall(&release.platform,&release.version)?;
+                    removed.push(release.clone());
+                }
+            }
+        }
+    }
+
+    Ok(removed)
 }
 
-#[cfg(feature = "cli")]
 pub fn get_installed() -> Result<HashSet<Release>,Error> {
     //! returns a HashSet of all installed releases.
     //! 
-    //! primarily for cli output
+    //! the link and checksum come from the install's manifest, installs from older versions
+    //! of lprun don't have one so `link` is empty.
 
     let mut releases : HashSet<Release> = HashSet::new();
     
-    let base_path = {
-        let mut path = lpsettings::get_folder();
-        let binary_path = lpsettings::get_value_or("run.binaries-root",&"bin".to_string());
-        path.push(binary_path.to_string());
-        path
-    };
+    let base_path = get_binaries_root();
+    if !base_path.exists() {
+        return Ok(releases);
+    }
 
     for entry in read_dir(base_path)? {
         let entry = entry?;
         if entry.path().is_dir() {
-            let platform : Platform = Platform::new(entry.path().file_name().unwrap().to_str().unwrap());
+            // folders that aren't named with utf-8 can't be a platform or a version
+            let platform : Platform = match entry.file_name().to_str() {
+                Some(name) => Platform::new(name),
+                None => continue,
+            };
             if platform != Platform::None {
                 for version_entry in read_dir(entry.path())? {
                     let version_entry = version_entry?;
-                    let version = Version::from_str(version_entry.path().file_name().unwrap().to_str().unwrap());
+                    let version = match version_entry.file_name().to_str() {
+                        Some(name) => Version::from_str(name),
+                        None => continue,
+                    };
                     if let Some(version)  = version {
-                        releases.insert(Release{
-                            platform : platform.clone(),
-                            version : version,
-                            link : "".to_string()
-                        });
+                        // the manifest knows where it came from, older installs don't.
+                        let (link, checksum) = match InstallManifest::load(version_entry.path()) {
+                            Some(manifest) => (manifest.link, manifest.checksum),
+                            None => ("".to_string(), None),
+                        };
+                        let mut release = Release::new(platform.clone(), version, &link);
+                        release.checksum = checksum;
+                        releases.insert(release);
                     }
                 }
             }
@@ -143,3 +305,169 @@ pub fn get_installed() -> Result<HashSet<Release>,Error> {
 
     Ok(releases)
 }
+
+fn get_binaries_root() -> PathBuf {
+    //! the folder all the binaries are installed in, defaults to ~/.lovepack/bin
+    
+    let mut path = lpsettings::get_folder();
+    let binary_path = lpsettings::get_value_or("run.binaries-root",&"bin".to_string());
+    path.push(binary_path.to_string());
+    path
+}
+
+fn install_to(platform : &Platform, version : &Version, install_path : &Path, download_folder : &Path, options : &InstallOptions) -> Result<PathBuf,Error> {
+    //! downloads into the download folder, then verifies and extracts the release into the 
+    //! install folder, returning the path to the extracted binary.
+    
+    let release = repo::get_release(platform,version)?;
+    info!("Installing from '{}'",release.link);
+    let download_path = download::download(&release.link, download_folder, release.size, options)?;
+    let download_file_name = download::file_name(&release.link)?;
+
+    options.check_cancelled()?;
+    options.report(Phase::Verify, 0, None);
+
+    // verifies the archive before extracting anything from it
+    let expected = match release.checksum {
+        Some(ref known) => Some(known.clone()),
+        None => checksum::from_manifest(&download_file_name)?,
+    };
+    match expected {
+        Some(ref expected) => checksum::verify(&download_path, expected)?,
+        None if checksum::is_required() => {
+            return Err(Error::ChecksumMissing { link : release.link.to_string() });
+        },
+        None => { warn!("No checksum known for '{}', it will not be verified.",release.link); },
+    }
+
+    let archive_checksum = checksum::sha256(&download_path)?;
+
+    options.check_cancelled()?;
+    options.report(Phase::Extract, 0, None);
+
+    let exe_path = if *platform == PartialPlatform::Mac {
+        if macos::is_disk_image(&download_file_name) {
+            macos::extract_dmg(&download_path, install_path)?;
+        } else {
+            archive_lp::extract_root_to(&download_path.display().to_string(), &install_path.display().to_string())
+                .map_err(|error| Error::extract(&download_path.display().to_string(), error))?;
+        }
+
+        // the binary is inside the bundle, not what the archive thinks is the binary
+        match macos::find_executable(install_path) {
+            Some(executable) => executable,
+            None => return Err(Error::extract(&download_path.display().to_string(), format_err!("no .app bundle found"))),
+        }
+    } else if appimage::is_appimage(&download_file_name) {
+        set_executable(&download_path)?;
+        if appimage::should_extract() {
+            appimage::extract(&download_path, install_path)?
+        } else {
+            // the AppImage is the binary
+            let exe_path = install_path.join(&download_file_name);
+            rename(&download_path, &exe_path)?;
+            exe_path
+        }
+    } else {
+        archive_lp::extract_root_to(&download_path.display().to_string(), &install_path.display().to_string())
+            .map_err(|error| Error::extract(&download_path.display().to_string(), error))?
+    };
+
+    set_executable(&exe_path)?;
+
+    // remembers what was installed, so nothing needs to be guessed later
+    let relative_exe = match exe_path.strip_prefix(install_path) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => return Err(Error::extract(&download_path.display().to_string(), 
+            format_err!("binary '{}' is outside of the install folder",exe_path.display().to_string()))),
+    };
+    InstallManifest::new(&release.link, Some(archive_checksum), relative_exe).save(install_path)?;
+
+    Ok(exe_path)
+}
+
+#[cfg(unix)]
+fn set_executable(path : &Path) -> Result<(),Error> {
+    //! makes sure the binary can be run, zip archives don't always keep the permissions.
+    
+    use std::fs::{metadata,set_permissions};
+    use std::os::unix::fs::PermissionsExt;
+
+    if !path.is_file() {
+        return Ok(());
+    }
+
+    let mut permissions = metadata(path)?.permissions();
+    permissions.set_mode(permissions.mode() | 0o111);
+    set_permissions(path, permissions)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path : &Path) -> Result<(),Error> {
+    Ok(())
+}
+
+fn get_download_folder(platform : &Platform, version : &Version) -> PathBuf {
+    //! the folder a version's archive is downloaded into, kept after a failed
+    //! install so the download can be resumed.
+    
+    let mut path = get_binaries_root();
+    path.push(".staging");
+    path.push("downloads");
+    path.push(platform.to_short_string());
+    path.push(version.to_string());
+    path
+}
+
+fn get_staging_folder(platform : &Platform, version : &Version) -> PathBuf {
+    //! the folder a version is installed into before being moved to its install folder, 
+    //! kept out of the platform folders so it is never mistaken for an install.
+    
+    let mut path = get_binaries_root();
+    path.push(".staging");
+    path.push(platform.to_short_string());
+    path.push(version.to_string());
+    path
+}
+
+pub fn get_install_folder(platform : &Platform, version : &Version) -> PathBuf {
+    //! the folder a specific version is installed in.
+    
+    let mut path = get_binaries_root();
+    path.push(platform.to_short_string());
+    path.push(version.to_string());
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::{temp_folder, touch};
+
+    fn mac() -> Platform {
+        Platform::iterator()
+            .map(|platform| platform.clone())
+            .find(|platform| *platform == PartialPlatform::Mac)
+            .unwrap()
+    }
+
+    #[test]
+    fn mac_path_defaults_to_love_app() {
+        let root = temp_folder("binary-mac-default");
+        let version = Version::from_str("11.3").unwrap();
+        let path = guess_path(&root, &mac(), &version).unwrap();
+
+        assert_eq!(path, root.join(mac().to_short_string()).join("11.3").join(macos::DEFAULT_EXECUTABLE));
+    }
+
+    #[test]
+    fn mac_path_uses_installed_bundle() {
+        let root = temp_folder("binary-mac-bundle");
+        let version = Version::from_str("11.3").unwrap();
+        let executable = root.join(mac().to_short_string()).join("11.3").join("love-11.3-macos/LÖVE.app/Contents/MacOS/love");
+        touch(&executable);
+
+        assert_eq!(guess_path(&root, &mac(), &version).unwrap(), executable);
+    }
+}
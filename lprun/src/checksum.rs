@@ -0,0 +1,80 @@
+use error::Error;
+
+use lpsettings;
+use sha2::{Sha256, Digest};
+
+use std::path::Path;
+use std::fs::File;
+use std::io::{self, Read};
+
+pub fn sha256<P : AsRef<Path>>(path : P) -> Result<String,Error> {
+    //! calculates the sha256 of a file, as a lowercase hex string
+    
+    let mut file = File::open(path.as_ref())?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+
+    Ok(format!("{:x}", hasher.result()))
+}
+
+pub fn verify<P : AsRef<Path>>(path : P, expected : &str) -> Result<(),Error> {
+    //! checks the file against the expected checksum, errors with 
+    //! `Error::Checksum` if it doesn't match.
+    
+    let expected = normalize(expected);
+    let actual = sha256(path.as_ref())?;
+
+    if actual == expected {
+        info!("Checksum for '{}' verified",path.as_ref().display().to_string());
+        Ok(())
+    } else {
+        Err(Error::Checksum { 
+            file : path.as_ref().display().to_string(), 
+            expected, 
+            actual 
+        })
+    }
+}
+
+pub fn from_manifest(file_name : &str) -> Result<Option<String>,Error> {
+    //! looks up the checksum for the file in the user's checksum manifest
+    //! 
+    //! the manifest is set with `lprun.checksums.file` and uses the same format
+    //! as `sha256sum`, one `<checksum>  <file name>` per line.
+
+    let manifest_path = match lpsettings::get_value("lprun.checksums.file")
+        .map_err(|error| Error::settings("lprun.checksums.file", error))? {
+        Some(lpsettings::Type::Text(path)) => path,
+        _ => return Ok(None),
+    };
+
+    let mut buffer = String::new();
+    File::open(&manifest_path)?.read_to_string(&mut buffer)?;
+
+    for line in buffer.lines() {
+        let mut parts = line.split_whitespace();
+        if let (Some(checksum), Some(name)) = (parts.next(), parts.next()) {
+            // sha256sum marks binary files with a `*` in front of the name
+            if name.trim_start_matches('*') == file_name {
+                return Ok(Some(normalize(checksum)));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+pub fn is_required() -> bool {
+    //! if installs without a known checksum should be refused, set with `lprun.checksums.required`
+    
+    match lpsettings::get_value_or("lprun.checksums.required",&false) {
+        lpsettings::Type::Switch(required) => required,
+        _ => false,
+    }
+}
+
+fn normalize(checksum : &str) -> String {
+    //! checksums can come as `sha256:<hex>` (like from github) or just `<hex>`
+    
+    checksum.trim().trim_start_matches("sha256:").to_lowercase()
+}
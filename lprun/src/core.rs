@@ -1,22 +1,32 @@
 use platform_lp::Platform;
 use version_lp::Version;
 
-use failure::Error;
+use error::Error;
 
 use std::path::{Path,PathBuf};
+use std::process::ExitStatus;
 use binary;
+use structs::options::{RunOptions, InstallOptions};
 
-pub fn run<P : AsRef<Path>>(plat : &Platform, ver : &Version, package_path : Option<P>) -> Result<(),Error> {
+pub fn run<P : AsRef<Path>>(plat : &Platform, ver : &Version, package_path : Option<P>, options : &RunOptions, install_options : &InstallOptions) -> Result<Option<ExitStatus>,Error> {
     //! runs love based on a ***platform*** and a ***version***
     //! 
-    //! will attempt to install a version of doesn't exist locally. if that initial install
-    //! fails then run will fail.
+    //! will attempt to install a version of doesn't exist locally, using the `install_options`.
+    //! if that initial install fails then run will fail.
+    //! 
+    //! waits for love to close and returns how it exited, unless `options.detach` is set, 
+    //! then it returns right after starting love with no status. the `options` also has
+    //! the arguments and environment the game is run with.
 
     let exe_path = PathBuf::from(binary::build_path(plat,ver)?);
-    if !exe_path.exists() {
+    // the install knows where the binary really ended up, which can be 
+    // different than the guess (like an AppImage or a nested app bundle)
+    let exe_path = if exe_path.exists() {
+        exe_path
+    } else {
         info!("love {} {} not found, attempting to install.",plat,ver);
-        binary::install(plat,ver)?;
-    }
+        binary::install_with(plat,ver,install_options)?
+    };
 
     let package = if let Some(path) = package_path {
         let path = PathBuf::from(path.as_ref());
@@ -26,5 +36,5 @@ pub fn run<P : AsRef<Path>>(plat : &Platform, ver : &Version, package_path : Opt
         None
     };
 
-    binary::run(exe_path,package)
+    binary::run(exe_path,package,options)
 }
\ No newline at end of file
@@ -0,0 +1,284 @@
+//! downloads releases, reporting the progress as it goes.
+//! 
+//! failed downloads are retried (`lprun.download.retries` times, waiting 
+//! `lprun.download.backoff` milliseconds, doubling each time) and pick up 
+//! where they left off if the server supports it.
+//! 
+//! `file://` links are copied instead.
+
+use error::Error;
+use structs::options::{InstallOptions, Phase};
+
+use http;
+use settings;
+use reqwest;
+use reqwest::StatusCode;
+use reqwest::header::{CONTENT_RANGE, RANGE};
+
+use std::path::{Path,PathBuf};
+use std::fs::{File,OpenOptions,metadata,remove_file};
+use std::io::{Read,Write};
+use std::thread;
+use std::time::Duration;
+
+// how much is read at a time, also how often progress is reported.
+static CHUNK_SIZE : usize = 64 * 1024;
+
+static DEFAULT_RETRIES : u32 = 3;
+static DEFAULT_BACKOFF_MS : u64 = 1000;
+
+pub fn download<P : AsRef<Path>>(link : &str, folder : P, size : Option<u64>, options : &InstallOptions) -> Result<PathBuf,Error> {
+    //! downloads the link into the folder, returning the path of the downloaded file.
+    //! 
+    //! the file is named the same as the last part of the link. if that file is already
+    //! there then it is assumed to be a partial download and is resumed. the `size`, if
+    //! it is known, is used to tell if the file there is already complete.
+    
+    let retries = settings::get_number("lprun.download.retries", DEFAULT_RETRIES);
+    let backoff = settings::get_number("lprun.download.backoff", DEFAULT_BACKOFF_MS);
+
+    download_with(&http::client()?, link, folder.as_ref(), size, retries, backoff, options)
+}
+
+fn download_with(client : &reqwest::Client, link : &str, folder : &Path, size : Option<u64>, retries : u32, backoff : u64, options : &InstallOptions) -> Result<PathBuf,Error> {
+    //! `download`, but with the client and how to retry given instead of coming from the settings.
+    
+    let path = folder.join(file_name(link)?);
+
+    if let Some(size) = size {
+        let existing = metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+        if existing > size {
+            warn!("'{}' is bigger than it should be, starting the download over",path.display().to_string());
+            remove_file(&path)?;
+        } else if existing == size && size > 0 {
+            info!("'{}' was already downloaded",path.display().to_string());
+            options.report(Phase::Download, existing, Some(size));
+            return Ok(path);
+        }
+    }
+
+    if let Some(source) = local_path(link) {
+        copy(link, &source, &path, options)?;
+        return Ok(path);
+    }
+
+    let mut attempt : u32 = 0;
+    loop {
+        match fetch(client, link, &path, options) {
+            Ok(()) => return Ok(path),
+            Err(error) => {
+                if attempt >= retries || !is_retryable(&error) {
+                    return Err(error);
+                }
+
+                let wait = backoff.saturating_mul(1 << attempt.min(16));
+                warn!("Download of '{}' failed ({}), retrying in {}ms",link,error,wait);
+                thread::sleep(Duration::from_millis(wait));
+                attempt += 1;
+            }
+        }
+    }
+}
+
+pub fn file_name(link : &str) -> Result<String,Error> {
+    //! the name of the file the link points to
+    
+    let url = reqwest::Url::parse(link).map_err(|error| Error::download(link, error))?;
+    
+    match url.path_segments().and_then(|segments| segments.last()) {
+        Some(name) if !name.is_empty() => Ok(name.to_string()),
+        _ => Err(Error::download(link, format_err!("can't tell the file name from the link"))),
+    }
+}
+
+pub fn local_path(link : &str) -> Option<PathBuf> {
+    //! the path of the file if the link is a `file://` url.
+    
+    reqwest::Url::parse(link).ok()
+        .filter(|url| url.scheme() == "file")
+        .and_then(|url| url.to_file_path().ok())
+}
+
+fn copy(link : &str, source : &Path, path : &Path, options : &InstallOptions) -> Result<(),Error> {
+    //! copies a local file, reporting it the same way as a download.
+    
+    let mut input = File::open(source).map_err(|error| Error::download(link, error))?;
+    let total = input.metadata().ok().map(|meta| meta.len());
+    let mut file = File::create(path)?;
+
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut done : u64 = 0;
+    options.report(Phase::Download, done, total);
+
+    loop {
+        options.check_cancelled()?;
+
+        let read = input.read(&mut buffer).map_err(|error| Error::download(link, error))?;
+        if read == 0 { break; }
+
+        file.write_all(&buffer[..read])?;
+        done += read as u64;
+        options.report(Phase::Download, done, total);
+    }
+
+    Ok(())
+}
+
+fn fetch(client : &reqwest::Client, link : &str, path : &Path, options : &InstallOptions) -> Result<(),Error> {
+    //! a single attempt at downloading, resuming from whatever is already in the file.
+    
+    let existing = metadata(path).map(|meta| meta.len()).unwrap_or(0);
+
+    let mut request = client.get(link);
+    if existing > 0 {
+        info!("Resuming download of '{}' from {} bytes",link,existing);
+        request = request.header(RANGE, format!("bytes={}-",existing));
+    }
+    let mut resp = request.send().map_err(|error| Error::network(link, error))?;
+
+    let (mut file, mut done, total) = match resp.status() {
+        // picks up where it left off
+        StatusCode::PARTIAL_CONTENT => {
+            let file = OpenOptions::new().append(true).open(path)?;
+            (file, existing, resp.content_length().map(|length| length + existing))
+        },
+        // the file might already be all there, but only if it is as long as the server's
+        StatusCode::RANGE_NOT_SATISFIABLE if existing > 0 => {
+            if content_range_total(&resp) == Some(existing) {
+                options.report(Phase::Download, existing, Some(existing));
+                return Ok(());
+            }
+            warn!("'{}' doesn't match what the server has, starting the download over",path.display().to_string());
+            remove_file(path)?;
+            return fetch(client, link, path, options);
+        },
+        // the server sent everything, so starts over
+        _ => {
+            resp = resp.error_for_status().map_err(|error| Error::network(link, error))?;
+            (File::create(path)?, 0, resp.content_length())
+        },
+    };
+
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    options.report(Phase::Download, done, total);
+    
+    loop {
+        options.check_cancelled()?;
+
+        let read = resp.read(&mut buffer).map_err(|error| Error::download(link, error))?;
+        if read == 0 { break; }
+
+        file.write_all(&buffer[..read])?;
+        done += read as u64;
+        options.report(Phase::Download, done, total);
+    }
+
+    if let Some(total) = total {
+        if done != total {
+            return Err(Error::download(link, format_err!("only got {} of {} bytes",done,total)));
+        }
+    }
+
+    Ok(())
+}
+
+fn content_range_total(resp : &reqwest::Response) -> Option<u64> {
+    //! the full length of the file from a `Content-Range` header, like `bytes */1234`
+    
+    resp.headers().get(CONTENT_RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.rsplit('/').next())
+        .and_then(|total| total.trim().parse::<u64>().ok())
+}
+
+fn is_retryable(error : &Error) -> bool {
+    //! if trying again might work, a cancel or a missing file won't get better.
+    
+    match *error {
+        Error::Cancelled => false,
+        Error::Network { ref cause, .. } => match cause.status() {
+            Some(status) => status.is_server_error(),
+            None => true,
+        },
+        Error::Download { .. } | Error::Io(_) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::{temp_folder, serve, response};
+
+    use reqwest::Client;
+    use std::fs::read;
+
+    fn archive() -> Vec<u8> {
+        (0 .. 200_000u32).map(|number| (number % 251) as u8).collect()
+    }
+
+    #[test]
+    fn resumes_dropped_download() {
+        let body = archive();
+        let half = body.len() / 2;
+        let served = body.clone();
+
+        let (url, server) = serve(2, move |number, request| {
+            if number == 0 {
+                // says it is sending everything, but drops the connection half way
+                response("200 OK", &[("Content-Length", served.len().to_string())], &served[..half])
+            } else {
+                assert!(request.to_lowercase().contains(&format!("range: bytes={}-",half)));
+                response("206 Partial Content", &[
+                    ("Content-Length", (served.len() - half).to_string()),
+                    ("Content-Range", format!("bytes {}-{}/{}",half,served.len() - 1,served.len())),
+                ], &served[half..])
+            }
+        });
+
+        let folder = temp_folder("download-resume");
+        let path = download_with(&Client::new(), &format!("{}/love-11.3-win64.zip",url), &folder, None, 1, 10, &InstallOptions::default()).unwrap();
+
+        assert_eq!(read(&path).unwrap(), body);
+        assert_eq!(server.join().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn restarts_when_range_does_not_match() {
+        let body = archive();
+        let served = body.clone();
+
+        let (url, server) = serve(2, move |number, _| {
+            if number == 0 {
+                response("416 Range Not Satisfiable", &[
+                    ("Content-Length", "0".to_string()),
+                    ("Content-Range", format!("bytes */{}",served.len())),
+                ], &[])
+            } else {
+                response("200 OK", &[("Content-Length", served.len().to_string())], &served)
+            }
+        });
+
+        // a leftover that is longer than the real file, so can't be resumed
+        let folder = temp_folder("download-416");
+        let path = folder.join("love-11.3-win64.zip");
+        File::create(&path).unwrap().write_all(&vec![7u8; body.len() + 10]).unwrap();
+
+        download_with(&Client::new(), &format!("{}/love-11.3-win64.zip",url), &folder, None, 0, 10, &InstallOptions::default()).unwrap();
+
+        assert_eq!(read(&path).unwrap(), body);
+        let requests = server.join().unwrap();
+        assert!(!requests[1].to_lowercase().contains("range:"));
+    }
+
+    #[test]
+    fn skips_download_of_complete_file() {
+        let folder = temp_folder("download-complete");
+        let path = folder.join("love-11.3-win64.zip");
+        File::create(&path).unwrap().write_all(&archive()).unwrap();
+
+        // nothing is listening, so this would fail if it tried
+        let link = "http://127.0.0.1:9/love-11.3-win64.zip";
+        assert_eq!(download_with(&Client::new(), link, &folder, Some(archive().len() as u64), 0, 10, &InstallOptions::default()).unwrap(), path);
+    }
+}
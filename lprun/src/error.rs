@@ -0,0 +1,127 @@
+//! the errors lprun can return, so users of the library can tell what went
+//! wrong without looking at the message.
+
+use failure;
+
+use reqwest;
+
+use std::io;
+
+/// Everything that can go wrong in lprun.
+/// 
+/// errors caused by something else (a failed request, a bad archive) keep
+/// that error as their `cause`.
+#[derive(Debug,Fail)]
+pub enum Error {
+    /// there is no release of that version for the platform.
+    #[fail(display = "No release of LOVE {} for {} found", version, platform)]
+    VersionNotFound { platform : String, version : String },
+    
+    /// the version isn't installed for the platform.
+    #[fail(display = "LOVE {} for {} is not installed.", version, platform)]
+    NotInstalled { platform : String, version : String },
+
+    /// lprun doesn't know how to install or run LOVE on the platform.
+    #[fail(display = "Platform '{}' isn't supported", platform)]
+    PlatformUnsupported { platform : String },
+
+    /// nothing said which version of LOVE should be used.
+    #[fail(display = "No version found, don't know what to run.")]
+    NoVersion,
+
+    /// a version or version requirement couldn't be understood.
+    #[fail(display = "Cannot parse version requirement '{}'", requirement)]
+    InvalidRequirement { requirement : String },
+
+    /// an argument or value given to lprun couldn't be understood.
+    #[fail(display = "{}", message)]
+    InvalidArgument { message : String },
+
+    /// downloading a release failed.
+    #[fail(display = "Failed to download '{}'", link)]
+    Download { link : String, #[cause] cause : failure::Error },
+
+    /// extracting a downloaded release failed.
+    #[fail(display = "Failed to extract '{}'", file)]
+    Extract { file : String, #[cause] cause : failure::Error },
+
+    /// the downloaded file isn't what was expected.
+    #[fail(display = "Checksum mismatch for '{}', expected {} but got {}", file, expected, actual)]
+    Checksum { file : String, expected : String, actual : String },
+
+    /// there is no checksum for the release, and one is required.
+    #[fail(display = "No checksum known for '{}', refusing to install it.", link)]
+    ChecksumMissing { link : String },
+
+    /// starting (or waiting on) LOVE failed.
+    #[fail(display = "Failed to run '{}'", path)]
+    Spawn { path : String, #[cause] cause : io::Error },
+
+    /// a request to a release source failed.
+    #[fail(display = "Request to '{}' failed", url)]
+    Network { url : String, #[cause] cause : reqwest::Error },
+
+    /// the local repo file or a release source's response couldn't be read.
+    #[fail(display = "Failed to read releases from '{}'", location)]
+    RepoParse { location : String, #[cause] cause : failure::Error },
+
+    /// the local repo file was written by a newer lprun.
+    #[fail(display = "'{}' has repo schema {}, but this lprun only reads up to {}. Update lprun or delete the file.", file, schema, supported)]
+    RepoSchema { file : String, schema : i64, supported : i64 },
+
+    /// something couldn't be turned into toml or json to be saved or printed.
+    #[fail(display = "Failed to write {}", what)]
+    Serialize { what : String, #[cause] cause : failure::Error },
+
+    /// something needed to be downloaded while in offline mode.
+    #[fail(display = "Offline, but would need to download {}", needed)]
+    Offline { needed : String },
+
+    /// the install was cancelled with its `CancelToken`.
+    #[fail(display = "Install was cancelled")]
+    Cancelled,
+
+    /// reading or writing a setting failed.
+    #[fail(display = "Failed to use setting '{}'", key)]
+    Settings { key : String, #[cause] cause : failure::Error },
+
+    /// reading or writing files failed.
+    #[fail(display = "{}", _0)]
+    Io(#[cause] io::Error),
+}
+
+impl Error {
+    pub(crate) fn download<E : Into<failure::Error>>(link : &str, cause : E) -> Error {
+        Error::Download { link : link.to_string(), cause : cause.into() }
+    }
+
+    pub(crate) fn extract<E : Into<failure::Error>>(file : &str, cause : E) -> Error {
+        Error::Extract { file : file.to_string(), cause : cause.into() }
+    }
+
+    pub(crate) fn network(url : &str, cause : reqwest::Error) -> Error {
+        Error::Network { url : url.to_string(), cause }
+    }
+
+    pub(crate) fn repo_parse<E : Into<failure::Error>>(location : &str, cause : E) -> Error {
+        Error::RepoParse { location : location.to_string(), cause : cause.into() }
+    }
+
+    pub(crate) fn serialize<E : Into<failure::Error>>(what : &str, cause : E) -> Error {
+        Error::Serialize { what : what.to_string(), cause : cause.into() }
+    }
+
+    pub(crate) fn settings<E : Into<failure::Error>>(key : &str, cause : E) -> Error {
+        Error::Settings { key : key.to_string(), cause : cause.into() }
+    }
+
+    pub(crate) fn invalid_argument<S : ToString>(message : S) -> Error {
+        Error::InvalidArgument { message : message.to_string() }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error : io::Error) -> Error {
+        Error::Io(error)
+    }
+}
@@ -0,0 +1,81 @@
+//! the http client used for everything, configured from the settings
+//! 
+//! - `lprun.http.proxy` a proxy all requests go through, like `http://proxy.local:3128`
+//! - `lprun.http.ca_file` a PEM file with an extra certificate authority to trust
+//! - `lprun.http.timeout` seconds before connecting or reading gives up, 0 never does
+
+use error::Error;
+use settings;
+
+use lpsettings;
+use reqwest;
+use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
+
+use std::fs::File;
+use std::io::Read;
+use std::sync::Mutex;
+use std::time::Duration;
+
+static DEFAULT_TIMEOUT_SECONDS : u64 = 30;
+
+// the client everything shares, made the first time it is needed.
+static CLIENT : Mutex<Option<reqwest::Client>> = Mutex::new(None);
+
+pub fn client() -> Result<reqwest::Client,Error> {
+    //! the client with the proxy, certificates and timeout from the settings.
+    //! 
+    //! it is only built once, every call after that gets the same client.
+    
+    let mut cached = CLIENT.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(ref client) = *cached {
+        return Ok(client.clone());
+    }
+
+    let client = build()?;
+    *cached = Some(client.clone());
+    Ok(client)
+}
+
+fn build() -> Result<reqwest::Client,Error> {
+    //! builds a client from the settings.
+    
+    let mut headers = HeaderMap::new();
+    // some apis (like github) refuse requests without a user agent
+    headers.insert(USER_AGENT, HeaderValue::from_static(concat!("lprun/", env!("CARGO_PKG_VERSION"))));
+
+    let timeout = match settings::get_number("lprun.http.timeout", DEFAULT_TIMEOUT_SECONDS) {
+        0 => None,
+        seconds => Some(Duration::from_secs(seconds)),
+    };
+
+    let mut builder = reqwest::Client::builder()
+        .default_headers(headers)
+        .timeout(timeout);
+
+    if let Some(proxy) = get_text("lprun.http.proxy")? {
+        info!("Using proxy '{}'",proxy);
+        let proxy = reqwest::Proxy::all(proxy.as_str())
+            .map_err(|error| Error::settings("lprun.http.proxy", error))?;
+        builder = builder.proxy(proxy);
+    }
+
+    if let Some(ca_file) = get_text("lprun.http.ca_file")? {
+        info!("Trusting certificates in '{}'",ca_file);
+        let mut buffer : Vec<u8> = Vec::new();
+        File::open(&ca_file)
+            .and_then(|mut file| file.read_to_end(&mut buffer))
+            .map_err(|error| Error::settings("lprun.http.ca_file", error))?;
+        let certificate = reqwest::Certificate::from_pem(&buffer)
+            .map_err(|error| Error::settings("lprun.http.ca_file", error))?;
+        builder = builder.add_root_certificate(certificate);
+    }
+
+    builder.build().map_err(|error| Error::settings("lprun.http", error))
+}
+
+fn get_text(key : &str) -> Result<Option<String>,Error> {
+    match lpsettings::get_value(key).map_err(|error| Error::settings(key, error))? {
+        Some(lpsettings::Type::Text(ref text)) if !text.trim().is_empty() => Ok(Some(text.trim().to_string())),
+        _ => Ok(None),
+    }
+}
@@ -9,21 +9,33 @@ use platform_lp::Platform;
 use version_lp::Version;
 use lpsettings;
 use love;
+use serde_json;
 
 use std::path::{PathBuf,Path};
+use std::process::ExitStatus;
+use std::cmp;
 
 use smart_hash::traits::SmartHashSet;
 
-use failure::Error;
+use error::Error;
 
 use core;
 use repo;
 use binary;
+use mirror;
+use offline;
+use pin;
+use resolve;
+use requirement::Requirement;
+use structs::options::{RunOptions, InstallOptions, Progress, Phase};
+
+// how many characters wide the install progress bar is
+static PROGRESS_WIDTH : usize = 30;
 
 // PUBLIC FUNCTIONS ////////////////////////////////////////////
 // should be accessable to the library user.
 
-pub fn process(matches : &clap::ArgMatches) -> Result<(),Error> {
+pub fn process(matches : &clap::ArgMatches) -> Result<Option<ExitStatus>,Error> {
     //! the main process function for ***run***
     //!
     //! processess all the switches and subcommands of ***run**
@@ -32,15 +44,27 @@ pub fn process(matches : &clap::ArgMatches) -> Result<(),Error> {
     //! - runs a version of love
     //! - can use the `-p` or `--platform` switch to force a certain platform
     //! - can use the `-v` or `--version` switch to force a certain version
+    //! - can use the `-d` or `--detach` switch to not wait for love to close
+    //! - can use `--env KEY=VALUE` to set environment variables for love
+    //! - can use `--offline` to never touch the network
+    //! - anything after `--` is passed to the game
+    //! 
+    //! returns the exit status of love when it was run and waited on.
 
     //! process install command, if used.
     if let Some(install) = matches.subcommand_matches("install") {
-        return process_install(&install);
+        process_install(&install)?;
+        return Ok(None);
+    }
+
+    if matches.is_present("offline") {
+        offline::set_offline(true);
     }
 
     // gets the project path, checks for the variable project.game-folder if the actual game is located
     // somewhere else
-    let package_path : Option<PathBuf> = if let Some(value) = lpsettings::get_value("project.game-folder")? {
+    let package_path : Option<PathBuf> = if let Some(value) = lpsettings::get_value("project.game-folder")
+        .map_err(|error| Error::settings("project.game-folder", error))? {
         if let Some(mut path) = get_path(&matches) {
             path.push(value.to_string());
             Some(path) // sets package_path to this
@@ -55,13 +79,14 @@ pub fn process(matches : &clap::ArgMatches) -> Result<(),Error> {
     let plat : Platform = get_platform(&matches);
 
     // gets the exectuion version
-    let ver : Option<Version> = get_version(&matches,&package_path);
+    let ver : Option<Version> = get_version(&matches,&plat,&package_path)?;
 
     // runs it.
     match ver {
-        None => Err(format_err!("No version found, don't know what to run.")),
+        None => Err(Error::NoVersion),
         Some(ref ver) => {
-            core::run(&plat,&ver,package_path)
+            let install_options = InstallOptions { progress : Some(Box::new(show_progress)), cancel : None };
+            core::run(&plat,&ver,package_path,&get_run_options(&matches)?,&install_options)
         }
     }
 }
@@ -83,7 +108,7 @@ pub fn app() -> clap::App<'static,'static> {
     //!
     //! match interface::process(&app) {
     //!     Err(error) => { println!("{}",error); }
-    //!     Ok(_) => { }
+    //!     Ok(_status) => { }
     //! }
     //! ```
     //!
@@ -119,9 +144,62 @@ pub fn app() -> clap::App<'static,'static> {
                     .arg(clap::Arg::with_name("list available")
                         .short("a")
                         .long("list-available")
-                        .help("Lists available binaries.")))
+                        .help("Lists available binaries."))
+                    .arg(clap::Arg::with_name("format")
+                        .long("format")
+                        .help("How the list is shown, `json` and `plain` are for other programs to read.")
+                        .value_name("FORMAT")
+                        .possible_values(&["table","json","plain"])
+                        .default_value("table")))
                 .subcommand(clap::SubCommand::with_name("update")
                     .about("Updates the local repository of LOVE releases."))
+                .subcommand(clap::SubCommand::with_name("remove")
+                    .about("Removes an installed version of LÖVE.")
+                    .arg(clap::Arg::with_name("version")
+                        .help("Version of LÖVE to remove.")
+                        .value_name("VERSION")
+                        .required(true)
+                        .index(1))
+                    .arg(clap::Arg::with_name("platform")
+                        .short("p")
+                        .long("platform")
+                        .help("Platform of the version to remove, defaults to the current platform.")
+                        .value_name("platform")))
+                .subcommand(clap::SubCommand::with_name("prune")
+                    .about("Removes old installed versions of LÖVE.")
+                    .arg(clap::Arg::with_name("keep")
+                        .short("k")
+                        .long("keep")
+                        .help("Number of the newest versions to keep for each platform.")
+                        .value_name("N")
+                        .default_value("1"))
+                    .arg(clap::Arg::with_name("project")
+                        .long("project")
+                        .help("Keeps the version this project uses, can be used multiple times.")
+                        .value_name("PROJECT")
+                        .multiple(true)
+                        .number_of_values(1)))
+                .subcommand(clap::SubCommand::with_name("mirror")
+                    .about("Downloads releases into a folder with an index, to use as a release source.")
+                    .arg(clap::Arg::with_name("folder")
+                        .help("Folder to put the releases and index in.")
+                        .value_name("DIR")
+                        .required(true)
+                        .index(1))
+                    .arg(clap::Arg::with_name("platform")
+                        .short("p")
+                        .long("platform")
+                        .help("Platform to mirror, can be used multiple times. Defaults to all platforms.")
+                        .value_name("platform")
+                        .multiple(true)
+                        .number_of_values(1))
+                    .arg(clap::Arg::with_name("versions")
+                        .long("versions")
+                        .help("Versions to mirror, like `11.3` or `>=0.10`, can be used multiple times. Defaults to all versions.")
+                        .value_name("VERSION")
+                        .multiple(true)
+                        .number_of_values(1)))
+                .setting(clap::AppSettings::SubcommandsNegateReqs)
             .arg(clap::Arg::with_name("version")
                 .help("Version of LÖVE to use, overrides PROJECT defined version.")
                 .value_name("VERSION")
@@ -146,11 +224,43 @@ pub fn app() -> clap::App<'static,'static> {
             .help("Override what platform to use, can only choose 32bit varients on 64 bit machines.")
             .value_name("platform"))
 
+        .arg(clap::Arg::with_name("offline")
+            .long("offline")
+            .global(true)
+            .help("Never uses the network, only installed versions and the cached repo are used."))
+
+        .arg(clap::Arg::with_name("resolution format")
+            .long("resolution-format")
+            .global(true)
+            .help("How the picked version is shown on stderr, `json` is for other programs to read.")
+            .value_name("FORMAT")
+            .possible_values(&["text","json"])
+            .default_value("text"))
+
+        .arg(clap::Arg::with_name("detach")
+            .short("d")
+            .long("detach")
+            .help("Starts LÖVE and returns right away instead of waiting for it to close."))
+
+        .arg(clap::Arg::with_name("env")
+            .short("e")
+            .long("env")
+            .help("Sets an environment variable for LÖVE, can be used multiple times.")
+            .value_name("KEY=VALUE")
+            .multiple(true)
+            .number_of_values(1))
+
     // arguements
         .arg(clap::Arg::with_name("PROJECT")
             .help("Path to LÖVE project folder or .love file")
             .value_name("PROJECT")
             .index(1))
+
+        .arg(clap::Arg::with_name("ARGS")
+            .help("Arguments passed to the game, must come after `--`")
+            .value_name("ARGS")
+            .multiple(true)
+            .last(true))
 }
 
 // PRIVATE FUNCTIONS ////////////////////////////////////////////
@@ -158,25 +268,105 @@ pub fn app() -> clap::App<'static,'static> {
 fn process_install(matches : &clap::ArgMatches) -> Result<(),Error> {
     //! handles all processing for the install subcommand
 
+    if matches.is_present("offline") {
+        offline::set_offline(true);
+    }
+
     if let Some(list) = matches.subcommand_matches("list") {
+        let format = match list.value_of("format") {
+            Some("json") => repo::Format::Json,
+            Some("plain") => repo::Format::Plain,
+            _ => repo::Format::Table,
+        };
         match list.is_present("list available") {
-            true => repo::list_available()?,
-            false => repo::list()?,
+            true => repo::list_available(&format)?,
+            false => repo::list(&format)?,
         }
     } else if let Some(_) = matches.subcommand_matches("update") {
-        repo::update_local_repo(true)?;
-    } else {
-        let platform = get_platform(matches);
-        if let Some(version) = matches.value_of("version") {
+        for update in repo::update_local_repo(true)? {
+            match update.result {
+                Ok(count) => println!("{} releases from '{}'",count,update.link),
+                Err(error) => eprintln!("Failed to update from '{}': {}",update.link,error),
+            }
+        }
+    } else if let Some(remove) = matches.subcommand_matches("remove") {
+        let platform = get_platform(remove);
+        if let Some(version) = remove.value_of("version") {
             match Version::from_str(version) {
                 Some(version) => { 
-                    binary::install(&platform, &version)?;
-                    println!("LOVE {} for {} installed.",version,platform);    
+                    binary::uninstall(&platform, &version)?;
+                    println!("LOVE {} for {} removed.",version,platform);    
                 },
-                None => { error!("Cannot parse version '{}'",version); },
+                None => return Err(Error::InvalidRequirement { requirement : version.to_string() }),
+            }
+        }
+    } else if let Some(prune) = matches.subcommand_matches("prune") {
+        let keep = match prune.value_of("keep").unwrap_or("1").parse::<usize>() {
+            Ok(keep) => keep,
+            Err(_) => return Err(Error::invalid_argument(format!("Cannot parse '{}' as a number to keep",prune.value_of("keep").unwrap()))),
+        };
+
+        // projects from the command line and from the settings are both kept.
+        let mut projects : Vec<PathBuf> = Vec::new();
+        if let Some(values) = prune.values_of("project") {
+            for value in values { projects.push(PathBuf::from(value)); }
+        }
+        match lpsettings::get_value("lprun.prune.projects").map_err(|error| Error::settings("lprun.prune.projects", error))? {
+            Some(lpsettings::Type::Text(project)) => projects.push(PathBuf::from(project)),
+            Some(lpsettings::Type::Array(array)) => {
+                for member in array {
+                    if let lpsettings::Type::Text(project) = member {
+                        projects.push(PathBuf::from(project));
+                    }
+                }
+            },
+            _ => (),
+        }
+
+        for release in binary::prune(keep, &projects)? {
+            println!("LOVE {} for {} removed.",release.version,release.platform);
+        }
+    } else if let Some(mirror) = matches.subcommand_matches("mirror") {
+        let folder = PathBuf::from(mirror.value_of("folder").unwrap());
+
+        let mut platforms : Vec<Platform> = Vec::new();
+        if let Some(values) = mirror.values_of("platform") {
+            for value in values {
+                match Platform::new(value) {
+                    Platform::None => return Err(Error::PlatformUnsupported { platform : value.to_string() }),
+                    platform => platforms.push(platform),
+                }
             }
+        }
+
+        let mut requirements : Vec<Requirement> = Vec::new();
+        if let Some(values) = mirror.values_of("versions") {
+            for value in values { requirements.push(value.parse()?); }
+        }
+
+        let options = InstallOptions { progress : Some(Box::new(show_progress)), cancel : None };
+        let result = mirror::mirror(&folder, &platforms, &requirements, &options)?;
+        for release in result.mirrored.iter() {
+            println!("LOVE {} for {} mirrored.",release.version,release.platform);
+        }
+        println!("Index written to '{}'.",folder.join(mirror::INDEX_FILE).display());
+
+        for &(ref release, ref error) in result.failed.iter() {
+            eprintln!("LOVE {} for {} failed: {}",release.version,release.platform,error);
+        }
+        if let Some((_, error)) = result.failed.into_iter().next() {
+            return Err(error);
+        }
+    } else {
+        let platform = get_platform(matches);
+        if let Some(version) = matches.value_of("version") {
+            let requirement : Requirement = version.parse()?;
+            let version = pick_version(matches, &platform, &requirement, "given on the command line")?;
+            let options = InstallOptions { progress : Some(Box::new(show_progress)), cancel : None };
+            binary::install_with(&platform, &version, &options)?;
+            println!("LOVE {} for {} installed.",version,platform);    
         } else {
-            return Err(format_err!("Cannot install LOVE if a version is not supplied."));
+            return Err(Error::invalid_argument("Cannot install LOVE if a version is not supplied."));
         }
     }
 
@@ -196,6 +386,50 @@ fn get_platform(matches : &clap::ArgMatches) -> Platform {
 
 }
 
+fn show_progress(progress : Progress) {
+    //! draws the install progress, on stderr so it doesn't get mixed up with the output.
+    
+    match progress.phase {
+        Phase::Download => match progress.total {
+            Some(total) if total > 0 => {
+                let filled = cmp::min(PROGRESS_WIDTH as u64, progress.done * PROGRESS_WIDTH as u64 / total) as usize;
+                eprint!("\rDownloading [{}{}] {:>3}%",
+                    "#".repeat(filled),
+                    " ".repeat(PROGRESS_WIDTH - filled),
+                    cmp::min(100, progress.done * 100 / total));
+            },
+            _ => { eprint!("\rDownloading {:.1} MB",progress.done as f64 / 1_000_000.0); },
+        },
+        Phase::Verify => { eprintln!("\nVerifying"); },
+        Phase::Extract => { eprintln!("Extracting"); },
+    }
+}
+
+fn get_run_options(matches : &clap::ArgMatches) -> Result<RunOptions,Error> {
+    //! builds the options to run love with from the switches and trailing arguments
+    
+    let mut options = RunOptions::default();
+    options.detach = matches.is_present("detach");
+
+    if let Some(args) = matches.values_of("ARGS") {
+        for arg in args { options.args.push(arg.to_string()); }
+    }
+
+    if let Some(envs) = matches.values_of("env") {
+        for env in envs {
+            let mut parts = env.splitn(2,'=');
+            match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) if !key.is_empty() => {
+                    options.env.push((key.to_string(), value.to_string()));
+                },
+                _ => return Err(Error::invalid_argument(format!("Cannot parse environment variable '{}', should be KEY=VALUE",env))),
+            }
+        }
+    }
+
+    Ok(options)
+}
+
 fn get_path(matches : &clap::ArgMatches) -> Option<PathBuf> {
     //! micro function to get the project path
     //! 
@@ -212,30 +446,90 @@ fn get_path(matches : &clap::ArgMatches) -> Option<PathBuf> {
     }
 }
 
-fn get_version(matches : &clap::ArgMatches, game_path: &Option<PathBuf>) -> Option<Version> {
+fn get_version(matches : &clap::ArgMatches, platform : &Platform, game_path: &Option<PathBuf>) -> Result<Option<Version>,Error> {
     //! gets the version to use, using rules
     //! 
     //! checks the order of version operations
     //! (1) checks if the version was given as an argument
-    //! (2) checks if the project that is being run has a version that can be used
-    //! (3) gets the latest installed version
+    //! (2) checks for a `.love-version` file, from the project or current folder upwards
+    //! (3) checks if the project that is being run has a version that can be used
+    //! (4) gets the latest installed version
+    //! 
+    //! (1) and (2) can be requirements like `^11.3` and will pick the newest matching version.
 
     // checks first is CLAP has a version
     if let Some(version_override) = matches.value_of("version") {
-        if let Some(version) = Version::from_str(&version_override) {
-            return Some(version);
-        }
+        let requirement : Requirement = version_override.parse()?;
+        return Ok(Some(pick_version(matches, platform, &requirement, "given on the command line")?));
+    }
+
+    // checks for a pin file
+    if let Some(pin_path) = pin::find(game_path.as_ref()) {
+        let requirement : Requirement = pin::read(&pin_path)?.parse()?;
+        let reason = format!("pinned in '{}'",pin_path.display().to_string());
+        return Ok(Some(pick_version(matches, platform, &requirement, &reason)?));
     }
 
     // checks the project for version information
     if let Some(ref path) = game_path {
         if let Ok(version_override_project) = love::project::get_required_version(path) {
-            return Some(version_override_project);
+            return Ok(Some(version_override_project));
         }
     }
 
     // if we are still here then use the latest installed version
-    get_latest_installed_version()
+    Ok(get_latest_installed_version())
+}
+
+fn pick_version(matches : &clap::ArgMatches, platform : &Platform, requirement : &Requirement, reason : &str) -> Result<Version,Error> {
+    //! gets the version for the requirement, and says which one was picked and why.
+    //! this goes to stderr so it doesn't get mixed up with the output.
+    //! 
+    //! exact requirements are used as is, so they can still be installed if needed.
+    
+    let exact = if requirement.is_exact() { Version::from_str(&requirement.to_string()) } else { None };
+    let resolution = match exact {
+        Some(version) => resolve::Resolution {
+            installed : binary::build_path(platform, &version).map(|path| path.exists()).unwrap_or(false),
+            platform : platform.clone(),
+            version,
+            requirement : requirement.clone(),
+        },
+        None => resolve::resolve(platform, requirement)?,
+    };
+
+    show_resolution(matches, &resolution, reason)?;
+    Ok(resolution.version)
+}
+
+fn show_resolution(matches : &clap::ArgMatches, resolution : &resolve::Resolution, reason : &str) -> Result<(),Error> {
+    //! shows which version was picked on stderr, as text or json.
+    
+    /// what the resolution looks like in the json output
+    #[derive(Serialize)]
+    struct JsonResolution {
+        platform : String,
+        version : String,
+        requirement : String,
+        installed : bool,
+        reason : String,
+    }
+
+    match matches.value_of("resolution format") {
+        Some("json") => {
+            let json = JsonResolution {
+                platform : resolution.platform.to_short_string().to_string(),
+                version : resolution.version.to_string(),
+                requirement : resolution.requirement.to_string(),
+                installed : resolution.installed,
+                reason : reason.to_string(),
+            };
+            eprintln!("{}",serde_json::to_string(&json).map_err(|error| Error::serialize("the resolution", error))?);
+        },
+        _ => { eprintln!("Using {}, {}",resolution,reason); },
+    }
+
+    Ok(())
 }
 
 fn get_latest_installed_version() -> Option<Version> {
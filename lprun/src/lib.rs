@@ -10,7 +10,6 @@ extern crate lpsettings;
 extern crate platform_lp;
 extern crate version_lp;
 extern crate archive_lp;
-extern crate download_lp;
 
 // for retrieveing release information and saving it
 extern crate reqwest;
@@ -20,6 +19,9 @@ extern crate toml;
 #[macro_use] extern crate serde_derive;
 extern crate serde;
 
+// for verifying downloads
+extern crate sha2;
+
 #[cfg(feature = "cli")]
 extern crate prettytable;
 
@@ -35,10 +37,49 @@ extern crate prettytable;
 pub mod interface;
 
 mod core;
+mod appimage;
 mod binary;
+mod checksum;
+mod download;
+mod error;
+mod http;
+mod macos;
+mod mirror;
+mod offline;
+// only the cli looks for version pins
+#[cfg(feature = "cli")]
+mod pin;
+mod requirement;
+mod resolve;
+mod settings;
 mod repo;
+mod sources;
 mod structs;
 
+#[cfg(test)]
+mod testing;
+
 // the public interface for the library
 pub use core::run as run;
-pub use binary::install as install;
\ No newline at end of file
+pub use structs::options::{RunOptions, InstallOptions, Progress, Phase, CancelToken};
+pub use requirement::Requirement;
+pub use resolve::{resolve, Resolution};
+pub use binary::install as install;
+pub use binary::install_with as install_with;
+pub use binary::uninstall as uninstall;
+pub use binary::prune as prune;
+pub use mirror::{mirror, Mirrored};
+pub use error::Error;
+pub use offline::{set_offline, is_offline};
+
+// for looking at what releases there are, doesn't need the cli feature
+pub use structs::release::{Release, ReleaseInfo};
+pub use binary::get_installed as installed;
+pub use repo::load_local_repo as available;
+pub use repo::update_local_repo as update;
+pub use repo::SourceUpdate;
+pub use repo::list_releases;
+
+// the types used in the public interface
+pub use platform_lp::Platform;
+pub use version_lp::Version;
\ No newline at end of file
@@ -0,0 +1,169 @@
+//! macOS releases are `.app` bundles, with the binary inside at 
+//! `love.app/Contents/MacOS/love`, and can come as a `.zip` or a `.dmg`
+
+use error::Error;
+
+use std::path::{Path,PathBuf};
+use std::process::Command;
+use std::fs::{create_dir_all,read_dir,remove_dir};
+
+/// where the binary is inside the install folder, if the bundle is named like normal.
+pub static DEFAULT_EXECUTABLE : &str = "love.app/Contents/MacOS/love";
+
+// how deep to look for the bundle, archives sometimes wrap it in a folder.
+static SEARCH_DEPTH : usize = 3;
+
+pub fn find_executable<P : AsRef<Path>>(folder : P) -> Option<PathBuf> {
+    //! finds the binary of the first `.app` bundle in the folder (or the folder itself if
+    //! it is a bundle), looking a few folders down if it needs to.
+
+    find_in(folder.as_ref(), SEARCH_DEPTH)
+}
+
+pub fn is_disk_image(file_name : &str) -> bool {
+    file_name.to_lowercase().ends_with(".dmg")
+}
+
+pub fn extract_dmg<P : AsRef<Path>, Q : AsRef<Path>>(image : P, destination : Q) -> Result<(),Error> {
+    //! copies the `.app` bundle out of the disk image into the destination folder
+    //! 
+    //! uses `hdiutil` to mount it, so only works on macOS.
+
+    let image = image.as_ref();
+    let destination = destination.as_ref();
+    let image_name = image.display().to_string();
+
+    if !cfg!(target_os = "macos") {
+        return Err(Error::extract(&image_name, format_err!("disk images can only be extracted on macOS")));
+    }
+
+    let mount_point = destination.join(".mount");
+    create_dir_all(&mount_point)?;
+
+    run_tool(Command::new("hdiutil")
+        .arg("attach").arg("-nobrowse").arg("-readonly")
+        .arg("-mountpoint").arg(&mount_point)
+        .arg(image), &image_name)?;
+
+    // always detaches, even if the copy failed
+    let copied = match find_bundle(&mount_point) {
+        None => Err(Error::extract(&image_name, format_err!("no .app bundle found in the disk image"))),
+        Some(bundle) => run_tool(Command::new("cp").arg("-R").arg(&bundle).arg(destination), &image_name),
+    };
+    let detached = run_tool(Command::new("hdiutil").arg("detach").arg(&mount_point), &image_name);
+
+    copied?;
+    detached?;
+    remove_dir(&mount_point)?;
+
+    Ok(())
+}
+
+fn find_in(folder : &Path, depth : usize) -> Option<PathBuf> {
+    let executable = folder.join("Contents").join("MacOS").join("love");
+    if executable.is_file() {
+        return Some(executable);
+    }
+
+    if depth == 0 {
+        return None;
+    }
+
+    if let Ok(entries) = read_dir(folder) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            if entry.path().is_dir() {
+                if let Some(executable) = find_in(&entry.path(), depth - 1) {
+                    return Some(executable);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+fn find_bundle(folder : &Path) -> Option<PathBuf> {
+    //! the bundle folder, like `love.app`, at the top of the folder
+    
+    if let Ok(entries) = read_dir(folder) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            let is_bundle = path.extension().map(|ext| ext == "app").unwrap_or(false);
+            if is_bundle && path.is_dir() {
+                return Some(path);
+            }
+        }
+    }
+
+    None
+}
+
+fn run_tool(command : &mut Command, image_name : &str) -> Result<(),Error> {
+    //! runs one of the tools used for the disk image, erroring if it didn't work.
+    
+    let status = command.status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::extract(image_name, format_err!("{:?} failed with {}",command,status)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::{temp_folder, touch};
+
+    #[test]
+    fn finds_default_bundle() {
+        let folder = temp_folder("macos-default");
+        touch(folder.join(DEFAULT_EXECUTABLE));
+
+        assert_eq!(find_executable(&folder), Some(folder.join(DEFAULT_EXECUTABLE)));
+    }
+
+    #[test]
+    fn finds_differently_named_bundle() {
+        let folder = temp_folder("macos-named");
+        let executable = folder.join("LÖVE 11.3.app/Contents/MacOS/love");
+        touch(&executable);
+
+        assert_eq!(find_executable(&folder), Some(executable));
+    }
+
+    #[test]
+    fn finds_nested_bundle() {
+        let folder = temp_folder("macos-nested");
+        let executable = folder.join("love-11.3-macos/love.app/Contents/MacOS/love");
+        touch(&executable);
+
+        assert_eq!(find_executable(&folder), Some(executable));
+    }
+
+    #[test]
+    fn finds_folder_that_is_bundle() {
+        let folder = temp_folder("macos-itself");
+        touch(folder.join("love.app/Contents/MacOS/love"));
+
+        assert_eq!(find_in(&folder.join("love.app"), 0), Some(folder.join("love.app/Contents/MacOS/love")));
+    }
+
+    #[test]
+    fn ignores_bundle_too_deep() {
+        let folder = temp_folder("macos-deep");
+        let mut nested = folder.clone();
+        for _ in 0 .. SEARCH_DEPTH { nested.push("wrapper"); }
+        touch(nested.join(DEFAULT_EXECUTABLE));
+
+        assert_eq!(find_executable(&folder), None);
+    }
+
+    #[test]
+    fn nothing_without_bundle() {
+        let folder = temp_folder("macos-none");
+        touch(folder.join("love.app/Contents/Resources/love.icns"));
+        touch(folder.join("readme.txt"));
+
+        assert_eq!(find_executable(&folder), None);
+    }
+}
@@ -0,0 +1,134 @@
+//! copies releases from the local repo into a folder, along with an index
+//! of them, so the folder can be used as a release source somewhere else.
+
+use error::Error;
+
+use platform_lp::Platform;
+
+use toml;
+
+use std::fs::{create_dir_all, metadata, remove_file, File};
+use std::io::Write;
+use std::path::Path;
+
+use structs::release::Release;
+use structs::options::{InstallOptions, Phase};
+use sources::{self, IndexEntry, IndexFile};
+use requirement::Requirement;
+
+use checksum;
+use download;
+use offline;
+use repo;
+
+pub static INDEX_FILE : &str = "index.toml";
+
+/// How mirroring went.
+pub struct Mirrored {
+    /// the releases that are in the folder and the index.
+    pub mirrored : Vec<Release>,
+    /// the releases that couldn't be mirrored, and why.
+    pub failed : Vec<(Release, Error)>,
+}
+
+pub fn mirror<P : AsRef<Path>>(folder : P, platforms : &[Platform], requirements : &[Requirement], options : &InstallOptions) -> Result<Mirrored,Error> {
+    //! downloads the releases from the local repo into the folder, and writes an
+    //! `index.toml` of the ones that worked.
+    //! 
+    //! only the `platforms` and versions matching one of the `requirements` are 
+    //! used, all of them if those are empty. if a release has more than one file
+    //! only the best one is mirrored. files already in the folder are resumed.
+    //! a release failing doesn't stop the others, they are all in the result.
+    
+    let folder = folder.as_ref();
+
+    let mut candidates : Vec<Release> = repo::load_local_repo()?.into_iter()
+        .filter(|release| platforms.is_empty() || platforms.contains(&release.platform))
+        .filter(|release| if release.prerelease {
+            // the same as resolving, prereleases have to be asked for exactly
+            requirements.iter().any(|requirement| requirement.is_exact() && requirement.matches(&release.version))
+        } else {
+            requirements.is_empty() || requirements.iter().any(|requirement| requirement.matches(&release.version))
+        })
+        .collect();
+    // the same order `repo::get_release` picks with, so the best file comes first.
+    candidates.sort_by_key(|release| (release.stale, release.prerelease, sources::extension_rank(release)));
+
+    let mut picked : Vec<Release> = Vec::new();
+    for release in candidates {
+        if !picked.iter().any(|other| other.platform == release.platform && other.version == release.version) {
+            picked.push(release);
+        }
+    }
+    picked.sort_by(|a, b| a.platform.to_short_string().cmp(&b.platform.to_short_string()).then(a.version.cmp(&b.version)));
+
+    if offline::is_offline() {
+        if let Some(release) = picked.iter().find(|release| download::local_path(&release.link).is_none()) {
+            return Err(Error::Offline { needed : format!("LOVE {} for {} from '{}'",release.version,release.platform,release.link) });
+        }
+    }
+
+    create_dir_all(folder)?;
+
+    let mut result = Mirrored { mirrored : Vec::new(), failed : Vec::new() };
+    let mut entries : Vec<IndexEntry> = Vec::new();
+    let mut cancelled = false;
+
+    for release in picked {
+        match mirror_release(&release, folder, options) {
+            Ok(entry) => {
+                entries.push(entry);
+                result.mirrored.push(release);
+            },
+            Err(Error::Cancelled) => {
+                cancelled = true;
+                break;
+            },
+            Err(error) => {
+                error!("Couldn't mirror {}: {}",release,error);
+                result.failed.push((release, error));
+            },
+        }
+    }
+
+    // the index always has what was mirrored, even if some failed or it was cancelled.
+    let index_path = folder.join(INDEX_FILE);
+    let toml_string = toml::to_string(&IndexFile { releases : entries })
+        .map_err(|error| Error::serialize(&index_path.display().to_string(), error))?;
+    let mut file = File::create(&index_path)?;
+    file.write_all(toml_string.as_bytes())?;
+
+    if cancelled {
+        return Err(Error::Cancelled);
+    }
+
+    Ok(result)
+}
+
+fn mirror_release(release : &Release, folder : &Path, options : &InstallOptions) -> Result<IndexEntry,Error> {
+    //! downloads and verifies one release, giving back its entry for the index.
+    
+    options.check_cancelled()?;
+    info!("Mirroring {} from '{}'",release,release.link);
+
+    let path = download::download(&release.link, folder, release.size, options)?;
+
+    options.report(Phase::Verify, 0, None);
+    if let Some(ref expected) = release.checksum {
+        if let Err(error) = checksum::verify(&path, expected) {
+            // a bad file can't be resumed, so the next mirror gets it again.
+            if let Err(cleanup_error) = remove_file(&path) {
+                error!("Failed to clean up '{}': {}",path.display().to_string(),cleanup_error);
+            }
+            return Err(error);
+        }
+    }
+
+    Ok(IndexEntry {
+        version : release.version.to_string(),
+        platform : release.platform.to_short_string().to_string(),
+        url : download::file_name(&release.link)?,
+        sha256 : Some(checksum::sha256(&path)?),
+        size : Some(metadata(&path)?.len()),
+    })
+}
@@ -0,0 +1,31 @@
+//! offline mode, when it is on nothing should touch the network.
+//! 
+//! can be turned on by the setting `lprun.offline` or for the running
+//! process with `set_offline`. resolution only uses installed versions
+//! and the cached repo file, anything that would need a download fails
+//! with `Error::Offline` saying what it would have downloaded.
+
+use lpsettings;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static FORCED : AtomicBool = AtomicBool::new(false);
+
+pub fn set_offline(offline : bool) {
+    //! turns offline mode on for everything this process does, regardless of the setting.
+    
+    FORCED.store(offline, Ordering::SeqCst);
+}
+
+pub fn is_offline() -> bool {
+    //! if we are offline, either set by `set_offline` or the `lprun.offline` setting.
+    
+    if FORCED.load(Ordering::SeqCst) {
+        return true;
+    }
+
+    match lpsettings::get_value_or("lprun.offline",&false) {
+        lpsettings::Type::Switch(offline) => offline,
+        _ => false,
+    }
+}
@@ -0,0 +1,111 @@
+//! version pin files, a `.love-version` file says what version of LOVE
+//! to use for everything in the folder it is in (and all sub folders).
+//! 
+//! works like `.ruby-version` or `.nvmrc`, the first line that isn't
+//! empty or a `#` comment is the version.
+
+use error::Error;
+
+use std::env;
+use std::path::{Path,PathBuf};
+use std::fs::{canonicalize,File};
+use std::io::Read;
+
+static PIN_FILE : &str = ".love-version";
+
+pub fn find<P : AsRef<Path>>(start : Option<P>) -> Option<PathBuf> {
+    //! looks for the pin file, starting at `start` (or the current directory) and 
+    //! walking up through the parent folders until it finds one.
+
+    let start = match start {
+        Some(path) => PathBuf::from(path.as_ref()),
+        None => match env::current_dir() {
+            Ok(path) => path,
+            Err(_) => return None,
+        }
+    };
+
+    // needs to be absolute so we can walk all the way up
+    let start = canonicalize(&start).unwrap_or(start);
+
+    // if the project is a file, like a .love, then starts at its folder
+    let mut folder : Option<&Path> = if start.is_file() { start.parent() } else { Some(&start) };
+
+    while let Some(path) = folder {
+        let pin = path.join(PIN_FILE);
+        if pin.is_file() {
+            return Some(pin);
+        }
+        folder = path.parent();
+    }
+
+    None
+}
+
+pub fn read<P : AsRef<Path>>(path : P) -> Result<String,Error> {
+    //! reads the version out of the pin file, as it was written.
+    
+    let mut buffer = String::new();
+    File::open(path.as_ref())?.read_to_string(&mut buffer)?;
+
+    for line in buffer.lines() {
+        let line = line.trim();
+        if !line.is_empty() && !line.starts_with('#') {
+            return Ok(line.to_string());
+        }
+    }
+
+    Err(Error::invalid_argument(format!("No version found in '{}'",path.as_ref().display().to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::{temp_folder, touch};
+
+    use std::io::Write;
+
+    fn pinned_tree(name : &str) -> PathBuf {
+        let root = canonicalize(temp_folder(name)).unwrap();
+        File::create(root.join(PIN_FILE)).unwrap()
+            .write_all(b"# the version for everything in here\n\n  11.3  \n0.10.2\n").unwrap();
+        root
+    }
+
+    #[test]
+    fn finds_pin_from_nested_folder() {
+        let root = pinned_tree("pin-nested");
+        let nested = root.join("games").join("platformer");
+        ::std::fs::create_dir_all(&nested).unwrap();
+
+        let pin = find(Some(&nested)).unwrap();
+        assert_eq!(pin, root.join(PIN_FILE));
+        assert_eq!(read(&pin).unwrap(), "11.3");
+    }
+
+    #[test]
+    fn finds_pin_from_love_file() {
+        let root = pinned_tree("pin-file");
+        let game = root.join("builds").join("game.love");
+        touch(&game);
+
+        assert_eq!(find(Some(&game)), Some(root.join(PIN_FILE)));
+    }
+
+    #[test]
+    fn closest_pin_wins() {
+        let root = pinned_tree("pin-closest");
+        let nested = root.join("old");
+        touch(nested.join(PIN_FILE));
+
+        assert_eq!(find(Some(nested.join("game.love"))), Some(nested.join(PIN_FILE)));
+    }
+
+    #[test]
+    fn pin_without_version() {
+        let root = canonicalize(temp_folder("pin-empty")).unwrap();
+        File::create(root.join(PIN_FILE)).unwrap().write_all(b"# nothing yet\n\n").unwrap();
+
+        assert!(read(root.join(PIN_FILE)).is_err());
+    }
+}
@@ -1,20 +1,25 @@
-use failure::Error;
+use error::Error;
 
-use platform_lp::{PartialPlatform, Platform};
+use platform_lp::Platform;
 use version_lp::Version;
 use lpsettings;
 
-use std::path::PathBuf;
+use std::path::{Path,PathBuf};
 use std::collections::HashSet;
-use std::fs::File;
+use std::fs::{self,File};
 use std::io::{Write,Read};
+use std::sync::{mpsc,Arc,Mutex};
+use std::thread;
+use std::time::{SystemTime,UNIX_EPOCH};
 
 use reqwest;
-use serde_json;
-use regex::Regex;
 use toml;
+#[cfg(feature = "cli")]
+use serde_json;
 
-use structs::release::{ Release, ReleaseExporter };
+use structs::release::{ Release, ReleaseInfo };
+use structs::repo_file::{ RepoFile, SourceInfo, PageInfo, SCHEMA_VERSION };
+use sources::{self, Page, Validators};
 
 use smart_hash::traits::SmartHashSet;
 
@@ -22,91 +27,394 @@ use smart_hash::traits::SmartHashSet;
 use prettytable;
 
 use binary;
+use http;
+use offline;
+use settings;
 
-// linux is the only one that can resolve without getting a full match
-// on platform, these should only be lowercase!
-static VALID_EXT_LINUX : [&str;3] = [ "appimage","tar.gz","tar.xz" ];
-static VALID_EXT_WINDOWS : [&str;1] = [ "zip" ];
-static VALID_EXT_MAC : [&str;2] = [ "zip","dmg" ];
-
-static REGEX_VERSION_MATCH : &str = r"(\d+[-|.|_]\d+[[-|.|_]\d+]*)";
 static REPO_FILE : &str = "love_repo.toml";
-static DEFAULT_LINKS : [&str;2] = [
+static DEFAULT_JOBS : usize = 4;
+static DEFAULT_LINKS : [&str;3] = [
+  "https://api.github.com/repos/love2d/love/releases",
   "https://api.bitbucket.org/2.0/repositories/rude/love/downloads",
   "https://api.bitbucket.org/2.0/repositories/snsvrno/love-linux-portable-binaries/downloads"
 ];
 
-pub fn get_version_link(platform : &Platform, version : &Version) -> Result<String,Error> {
-    //! checks the local repo file for the download link for the requested version
+pub fn get_release(platform : &Platform, version : &Version) -> Result<Release,Error> {
+    //! checks the local repo file for the release of the requested version,
+    //! which has the download link and checksum (if known).
     
     let releases : HashSet<Release> = load_local_repo()?;
 
-    match get_matching!(releases,version == version.clone(), platform == platform.clone()) {
-        None => Err(format_err!("No link found for {}-{}",version,platform)),
-        Some(links) => Ok(links[0].link.to_string()),
+    match get_matching!(releases,version == version.clone(), platform == platform.clone()).and_then(pick_best) {
+        None => Err(Error::VersionNotFound { platform : platform.to_string(), version : version.to_string() }),
+        Some(release) => Ok(release.clone()),
     }
 }
 
-pub fn update_local_repo(forced : bool) -> Result<(),Error> {
-    //! queries the repo links and updates the local repo file
+fn pick_best(mut releases : Vec<&Release>) -> Option<&Release> {
+    //! there can be more than one file for a release, so picks the best kind,
+    //! preferring ones that were found the last update and aren't prereleases.
+    
+    releases.sort_by_key(|release| (release.stale, release.prerelease, sources::extension_rank(release)));
+    releases.into_iter().next()
+}
+
+/// How updating from one of the repo links went.
+pub struct SourceUpdate {
+    pub link : String,
+    /// how many releases the source has, or why it couldn't be read.
+    pub result : Result<usize,Error>,
+}
+
+pub fn update_local_repo(forced : bool) -> Result<Vec<SourceUpdate>,Error> {
+    //! queries the repo links and updates the local repo file, giving back how
+    //! each link went.
     //! 
     //! will only run based on the frequency in lpsettings, or if 
-    //! the `forced` bool is used.
+    //! the `forced` bool is used. never runs when offline. nothing is given back
+    //! if it didn't run.
+    //! 
+    //! the sources are fetched at the same time, and one failing doesn't stop
+    //! the others. it is only an error if every source failed. pages that haven't
+    //! changed since the last update (by their `ETag` or `Last-Modified`) aren't
+    //! downloaded again.
+    //! 
+    //! releases that weren't found this time are kept but marked as stale, only
+    //! the releases of links that were removed from the settings are dropped.
     
+    let mut updates : Vec<SourceUpdate> = Vec::new();
+
     if lpsettings::update::check_if_should_update("lprun.repo") || forced {
         let repo_path = get_repo_path();
-        let mut links = get_repo_links();
+        let links = get_repo_links();
 
-        let mut releases : HashSet<Release> = HashSet::new();
-        
-        loop {
-            match links.pop() {
-                None => break,
-                Some(link) => {
-                    // do something here, to find the link
-                    if let Some(additional_link) = process_bitbucket(&mut releases, &link)? { 
-                        links.push(additional_link); 
-                    }
-                }
+        if offline::is_offline() {
+            // only an error if it was asked for, otherwise the old repo is fine.
+            if forced || !repo_path.exists() {
+                return Err(Error::Offline { needed : format!("the release lists from {}",links.join(", ")) });
             }
+            info!("Offline, not updating the local repo");
+            return Ok(Vec::new());
         }
 
-        // saves the file.
+        // what was fetched last time, so unchanged pages can be skipped.
+        let previous = if repo_path.exists() {
+            match read_repo_file(&repo_path) {
+                Ok(previous) => previous,
+                // never overwrites a newer lprun's file.
+                Err(error @ Error::RepoSchema { .. }) => return Err(error),
+                Err(error) => {
+                    error!("Couldn't read the local repo, getting everything again: {}",error);
+                    RepoFile::default()
+                },
+            }
+        } else {
+            RepoFile::default()
+        };
+
+        let mut releases : Vec<Release> = Vec::new();
+        let mut source_infos : Vec<SourceInfo> = Vec::new();
+
+        for (link, result) in fetch_sources(links.clone(), &previous, &http::client()?) {
+            match result {
+                Ok((info, found)) => {
+                    info!("Got {} releases from '{}'",found.len(),link);
+                    updates.push(SourceUpdate { link, result : Ok(found.len()) });
+                    releases.extend(found);
+                    source_infos.push(info);
+                },
+                Err(error) => {
+                    error!("Couldn't get releases from '{}': {}",link,error);
+                    // keeps what we had so the next update can still be conditional.
+                    if let Some(info) = previous.source(&link) { source_infos.push(info.clone()); }
+                    updates.push(SourceUpdate { link, result : Err(error) });
+                },
+            }
+        }
+
+        if !updates.is_empty() && updates.iter().all(|update| update.result.is_err()) {
+            if let Some(error) = updates.into_iter().filter_map(|update| update.result.err()).next() {
+                return Err(error);
+            }
+        }
+
+        let releases = merge_releases(releases, previous.releases, &links);
+        write_repo_file(&repo_path, &RepoFile::new(source_infos, releases))?;
+    }
+
+    lpsettings::update::set_last_update_as_now("lprun.repo")
+        .map_err(|error| Error::settings("lprun.repo", error))?;
+    
+    Ok(updates)
+}
+
+fn merge_releases(found : Vec<Release>, previous : Vec<Release>, links : &[String]) -> HashSet<Release> {
+    //! adds the releases from the last update that weren't `found` again,
+    //! marked as stale. ones from links that aren't used anymore are dropped,
+    //! and so are ones that don't know their link (from schema 0 repo files).
+    
+    let found_links : HashSet<String> = found.iter().map(|release| release.link.clone()).collect();
+    let mut releases : HashSet<Release> = found.into_iter().collect();
+
+    for mut release in previous {
+        if found_links.contains(&release.link) { continue; }
+        match release.source {
+            Some(ref source) if links.contains(source) => (),
+            _ => continue,
+        }
+
+        if !release.stale { info!("Release {} wasn't found, marking it as stale",release); }
+        release.stale = true;
+        releases.insert(release);
+    }
+
+    releases
+}
+
+fn fetch_sources(links : Vec<String>, previous : &RepoFile, client : &reqwest::Client) -> Vec<(String,Result<(SourceInfo,Vec<Release>),Error>)> {
+    //! fetches all the sources, a few at a time, and gives back how each one went.
+    //! 
+    //! how many are fetched at once can be set with `lprun.repo.jobs`.
+
+    let jobs : Vec<(String,Vec<PageInfo>,Vec<Release>)> = links.into_iter()
+        .map(|link| {
+            let pages = previous.source(&link).map(|info| info.pages.clone()).unwrap_or_default();
+            let known = previous.releases.iter()
+                .filter(|release| release.source.as_ref() == Some(&link))
+                .cloned()
+                .collect();
+            (link, pages, known)
+        }).collect();
+    let workers = settings::get_number("lprun.repo.jobs", DEFAULT_JOBS).max(1).min(jobs.len());
+
+    let queue = Arc::new(Mutex::new(jobs.into_iter()));
+    let (sender, receiver) = mpsc::channel();
+    let mut handles = Vec::new();
+
+    for _ in 0 .. workers {
+        let queue = queue.clone();
+        let sender = sender.clone();
+        let client = client.clone();
+
+        handles.push(thread::spawn(move || loop {
+            let job = queue.lock().ok().and_then(|mut jobs| jobs.next());
+            match job {
+                None => break,
+                Some((link, cached, known)) => {
+                    let result = fetch_source(&link, &cached, &known, &client);
+                    if sender.send((link, result)).is_err() { break; }
+                },
+            }
+        }));
+    }
+    drop(sender);
 
-        let mut file = File::create(&repo_path)?;
+    let results = receiver.iter().collect();
+    for handle in handles {
+        if handle.join().is_err() { error!("A thread fetching releases panicked"); }
+    }
+    results
+}
 
-        {
-            let export = ReleaseExporter::from_release(releases);
-            let toml_string = toml::to_string(&export)?;
-            file.write(toml_string.as_bytes())?;
+fn fetch_source(link : &str, cached : &[PageInfo], known : &[Release], client : &reqwest::Client) -> Result<(SourceInfo,Vec<Release>),Error> {
+    //! pages through a source until it runs out of pages, reusing the
+    //! `known` releases for pages the source says haven't changed.
+    
+    let source = match sources::from_link(link, client) {
+        Some(source) => source,
+        None => return Err(Error::invalid_argument(format!("Don't know how to get releases from '{}'",link))),
+    };
+
+    let mut pages : Vec<PageInfo> = Vec::new();
+    let mut releases : Vec<Release> = Vec::new();
+    let mut cursor : Option<String> = None;
+    loop {
+        let previous = cached.iter().find(|page| page.cursor == cursor);
+        let validators = previous.map(|page| page.validators()).unwrap_or_default();
+        let cursor_str = cursor.as_ref().map(|c| c.as_str());
+
+        let page = match source.fetch_page(cursor_str, &validators)? {
+            Some(page) => page,
+            None => match previous.and_then(|page| reuse_page(page, known)) {
+                Some(page) => {
+                    info!("'{}' hasn't changed",link);
+                    page
+                },
+                // the releases on it were lost, so gets the whole page again.
+                None => source.fetch_page(cursor_str, &Validators::default())?
+                    .ok_or_else(|| Error::repo_parse(link, format_err!("page wasn't modified, but nothing is cached")))?,
+            },
+        };
+
+        pages.push(PageInfo {
+            cursor : cursor.clone(),
+            next : page.next.clone(),
+            etag : page.validators.etag,
+            last_modified : page.validators.last_modified,
+            links : page.releases.iter().map(|release| release.link.clone()).collect(),
+        });
+        for mut release in page.releases {
+            release.source = Some(link.to_string());
+            release.stale = false;
+            releases.push(release);
         }
 
+        cursor = page.next;
+        if cursor.is_none() { break; }
+    }
+
+    let last_fetched = SystemTime::now().duration_since(UNIX_EPOCH).ok().map(|time| time.as_secs());
+    Ok((SourceInfo { link : link.to_string(), last_fetched, pages }, releases))
+}
+
+fn reuse_page(page : &PageInfo, known : &[Release]) -> Option<Page> {
+    //! rebuilds a page from the releases already known, `None` if any are missing.
+
+    let mut releases : Vec<Release> = Vec::new();
+    for link in page.links.iter() {
+        releases.push(known.iter().find(|release| &release.link == link)?.clone());
     }
 
-    lpsettings::update::set_last_update_as_now("lprun.repo")?;
+    Some(Page { releases, next : page.next.clone(), validators : page.validators() })
+}
+
+pub fn list_releases(include_available : bool) -> Result<Vec<ReleaseInfo>,Error> {
+    //! gets the information for all installed releases, and all releases in the local
+    //! repo if `include_available` is used.
+    //! 
+    //! sorted by platform, with the newest versions first. the link is where an
+    //! installed release came from, or the file `get_release` would install.
     
-    Ok(())
+    let installed = binary::get_installed()?;
+    let known = if include_available { load_local_repo()? } else { load_cached_repo() };
+
+    let mut listing : Vec<ReleaseInfo> = Vec::new();
+
+    for platform_in_question in Platform::iterator() {
+        let current = platform_in_question.clone();
+        let mut versions : Vec<Version> = Vec::new();
+        
+        if let Some(release_set) = get_matching!(installed,platform == current.clone()) {
+            for release in release_set { versions.push(release.version.clone()); }
+        }
+        if include_available {
+            if let Some(release_set) = get_matching!(known,platform == current.clone()) {
+                for release in release_set {
+                    if !versions.contains(&release.version) { versions.push(release.version.clone()); }
+                }
+            }
+        }
+
+        versions.sort();
+        versions.reverse();
+
+        for version in versions {
+            let installed_release = get_matching!(installed,platform == current.clone(),version == version.clone())
+                .and_then(|releases| releases.into_iter().next());
+
+            // installs from older versions of lprun don't know their link.
+            let link = match installed_release {
+                Some(release) if !release.link.is_empty() => Some(release.link.to_string()),
+                _ => get_matching!(known,platform == current.clone(),version == version.clone())
+                    .and_then(pick_best)
+                    .map(|release| release.link.to_string()),
+            };
+
+            listing.push(ReleaseInfo {
+                installed : installed_release.is_some(),
+                path : binary::get_install_folder(&current,&version),
+                platform : current.clone(),
+                version,
+                link,
+            });
+        }
+    }
+
+    Ok(listing)
+}
+
+/// How lists of releases are shown, cli feature
+#[cfg(feature = "cli")]
+pub enum Format {
+    /// a table with a column for each platform
+    Table,
+    /// a json array of all the release information
+    Json,
+    /// one release per line, tab separated
+    Plain,
 }
 
 #[cfg(feature = "cli")]
-pub fn list() -> Result<(),Error> { 
-    //! creates a table of all the installed LOVE releases, cli feature
+pub fn list(format : &Format) -> Result<(),Error> { 
+    //! shows all the installed LOVE releases, cli feature
     
-    let releases = binary::get_installed()?;
-    create_table(releases, None);
+    match *format {
+        Format::Table => {
+            let releases = binary::get_installed()?;
+            create_table(releases, None);
+        },
+        _ => print_listing(list_releases(false)?, format)?,
+    }
     Ok(())
 }
 
 #[cfg(feature = "cli")]
-pub fn list_available() -> Result<(),Error> {
-    //! creates a table of all LOVE releases, cli feature
+pub fn list_available(format : &Format) -> Result<(),Error> {
+    //! shows all LOVE releases, cli feature
     //! 
     //! will display all platforms organized.
     
-    let releases = load_local_repo()?;
-    let locally_installed = binary::get_installed()?;
+    match *format {
+        Format::Table => {
+            let releases = load_local_repo()?;
+            let locally_installed = binary::get_installed()?;
+            create_table(releases, Some(locally_installed));
+        },
+        _ => print_listing(list_releases(true)?, format)?,
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "cli")]
+fn print_listing(listing : Vec<ReleaseInfo>, format : &Format) -> Result<(),Error> {
+    //! prints the listing in a format that is easy for other programs to read, cli feature
+    
+    /// what each release looks like in the json output
+    #[derive(Serialize)]
+    struct JsonRelease {
+        platform : String,
+        version : String,
+        link : Option<String>,
+        path : String,
+        installed : bool,
+    }
 
-    create_table(releases, Some(locally_installed));
+    match *format {
+        Format::Json => {
+            let mut json : Vec<JsonRelease> = Vec::new();
+            for info in listing {
+                json.push(JsonRelease {
+                    platform : info.platform.to_short_string().to_string(),
+                    version : info.version.to_string(),
+                    link : info.link,
+                    path : info.path.display().to_string(),
+                    installed : info.installed,
+                });
+            }
+            println!("{}",serde_json::to_string_pretty(&json).map_err(|error| Error::serialize("the release listing", error))?);
+        },
+        _ => {
+            for info in listing {
+                println!("{}\t{}\t{}\t{}\t{}",
+                    info.platform.to_short_string(),
+                    info.version,
+                    if info.installed { "installed" } else { "available" },
+                    info.path.display().to_string(),
+                    info.link.unwrap_or_default());
+            }
+        },
+    }
 
     Ok(())
 }
@@ -151,11 +459,14 @@ fn create_table(main_list : HashSet<Release>, highlight_list : Option<HashSet<Re
     table.printstd();
 }
 
-fn load_local_repo() -> Result<HashSet<Release>,Error> {
+pub fn load_local_repo() -> Result<HashSet<Release>,Error> {
     //! loads the repo file into memory, as a HashSet of Release
     //! 
-    //! Uses the interim data structure `ReleaseExporter` because of
-    //! an issue with directly saving and reading the HashSet
+    //! files written by older versions of lprun are migrated and saved
+    //! in the current schema, files from newer versions are an error.
+    //! 
+    //! if there isn't a repo file yet it will be downloaded first, which is an
+    //! error when offline.
     
     let repo_path = get_repo_path();
 
@@ -163,12 +474,84 @@ fn load_local_repo() -> Result<HashSet<Release>,Error> {
         update_local_repo(true)?;
     }
 
+    let mut repo = read_repo_file(&repo_path)?;
+    if repo.schema < SCHEMA_VERSION {
+        info!("Migrating the local repo from schema {} to {}",repo.schema,SCHEMA_VERSION);
+        repo.migrate();
+        // the migrated releases are still usable if it can't be saved.
+        if let Err(error) = write_repo_file(&repo_path, &repo) {
+            error!("Couldn't save the migrated local repo: {}",error);
+        }
+    }
+
+    Ok(repo.releases.into_iter().collect())
+}
+
+fn load_cached_repo() -> HashSet<Release> {
+    //! loads the repo file if it was already downloaded, never updates it.
+    //! 
+    //! an empty set if there isn't one or it couldn't be read.
+    
+    let repo_path = get_repo_path();
+
+    if !repo_path.exists() {
+        return HashSet::new();
+    }
+
+    match read_repo_file(&repo_path) {
+        Ok(mut repo) => {
+            repo.migrate();
+            repo.releases.into_iter().collect()
+        },
+        Err(error) => {
+            error!("Couldn't read the local repo: {}",error);
+            HashSet::new()
+        }
+    }
+}
+
+fn read_repo_file(repo_path : &Path) -> Result<RepoFile,Error> {
+    //! reads the repo file, as it was saved.
+    //! 
+    //! checks the schema first, so a file from a newer lprun gives a clear
+    //! error instead of failing to parse.
+
+    let location = repo_path.display().to_string();
+
     let mut file = File::open(&repo_path)?;
     let mut buffer : String = String::new();
     file.read_to_string(&mut buffer)?;
 
-    let export : ReleaseExporter = toml::from_str(&buffer)?;
-    Ok(export.to_release())
+    let value : toml::Value = toml::from_str(&buffer)
+        .map_err(|error| Error::repo_parse(&location, error))?;
+
+    let schema = value.get("schema").and_then(|schema| schema.as_integer()).unwrap_or(0);
+    if schema > SCHEMA_VERSION {
+        return Err(Error::RepoSchema { file : location, schema, supported : SCHEMA_VERSION });
+    }
+
+    value.try_into()
+        .map_err(|error| Error::repo_parse(&location, error))
+}
+
+fn write_repo_file(repo_path : &Path, repo : &RepoFile) -> Result<(),Error> {
+    //! saves the repo file.
+    //! 
+    //! writes to a temporary file first and then moves it over the repo file, 
+    //! so an interrupted save never leaves half of a file behind.
+    
+    let toml_string = toml::to_string(repo)
+        .map_err(|error| Error::serialize(&repo_path.display().to_string(), error))?;
+
+    let temp_path = repo_path.with_extension("toml.tmp");
+    {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(toml_string.as_bytes())?;
+        file.sync_all()?;
+    }
+    fs::rename(&temp_path, repo_path)?;
+
+    Ok(())
 }
 
 fn get_repo_path() -> PathBuf {
@@ -185,6 +568,9 @@ fn get_repo_path() -> PathBuf {
 fn get_repo_links() -> Vec<String> {
     //! gets the list of links to check, (1) will use the default ones unless told not to
     //! and (2) will load additional ones if they are available.
+    //! 
+    //! custom links can be prefixed with the source type to use, i.e. `bitbucket+https://...`
+    //! or `github+http://localhost:8000/repos/love2d/love/releases`
     
     let mut links : Vec<String> = Vec::new();
 
@@ -210,95 +596,107 @@ fn get_repo_links() -> Vec<String> {
     links
 }
 
-fn process_bitbucket(repo_obj : &mut HashSet<Release>, url : &str) -> Result<Option<String>,Error> {
-    //! gets links from bitbucket repos
-    
-    if !url.contains("bitbucket") { return Ok(None); }
-
-    let mut resp = reqwest::get(url)?;
-    let raw_json = resp.text()?;
-    let json : serde_json::Value = serde_json::from_str(&raw_json)?;
-
-    if let Some(json_releases) = json["values"].as_array() {
-        let re_version = Regex::new(REGEX_VERSION_MATCH).unwrap();
-        for download in json_releases {
-            println!("Download.name: {}",download["name"]);
-            if let Some(version_cap) = re_version.captures(download["name"].as_str().unwrap()) {
-                match Version::from_str(version_cap.get(1).unwrap().as_str()) {
-                    None => { error!("Error parsing version {:?}",version_cap.get(1).unwrap()); },
-                    Some(version) => {
-                        let link = download["links"]["self"]["href"].as_str().unwrap();
-                        println!("Link: {}",link);
-
-                        // resolves the platform, does it this way because there is some nuance it it,
-                        // because some of the files don't have platforms, but the extension (like AppImage)
-                        // gives it away.
-                        // also some releases are installable, and we don't want those, we want the 
-                        // 'portable' zipped archive release instead.
-                        let platform = {
-
-                            let mut platform_guess = Platform::new(download["name"].as_str().unwrap());
-                            let mut valid = false;
-
-                            // checks if valid linux platform
-                            if platform_guess == PartialPlatform::Linux {
-                                for part in &VALID_EXT_LINUX {
-                                    if link.to_lowercase().contains(part) {
-                                        valid = true; break;
-                                    }
-                                }
-                            } else if platform_guess == PartialPlatform::Windows {
-                                for part in &VALID_EXT_WINDOWS {
-                                    if link.to_lowercase().contains(part) {
-                                        valid = true; break;
-                                    }
-                                }
-                            } else if platform_guess == PartialPlatform::Mac {
-                                for part in &VALID_EXT_MAC {
-                                    if link.to_lowercase().contains(part) {
-                                        valid = true; break;
-                                    }
-                                }
-                            } else if platform_guess == Platform::None {
-                                for part in &VALID_EXT_LINUX {
-                                    if link.to_lowercase().contains(part) {
-                                        if link.to_lowercase().contains("686") || link.to_lowercase().contains("32") {
-                                            platform_guess = Platform::Nix32;
-                                            valid = true;
-                                        } else if link.to_lowercase().contains("64") {
-                                            platform_guess = Platform::Nix64;
-                                            valid = true;
-                                        }
-                                        break;
-                                    }
-                                } 
-                            }
-
-                            if valid {
-                                platform_guess
-                            } else {
-                                Platform::None
-                            }
-                        };
-
-                        if platform == Platform::None {
-                            error!("Error parsing platform {}",download["name"].as_str().unwrap());
-                        } else {
-                            let link = link.to_string();
-                            let release = Release { version, platform, link };
-                            info!("Found release {}",release);
-                            repo_obj.insert(release);
-                        }
-                    }
-                }
-                
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::temp_folder;
+
+    static GITHUB : &str = "https://api.github.com/repos/love2d/love/releases";
+    static BITBUCKET : &str = "https://api.bitbucket.org/2.0/repositories/rude/love/downloads";
+
+    fn release(version : &str, link : &str, source : Option<&str>) -> Release {
+        let mut release = Release::new(Platform::Win64, Version::from_str(version).unwrap(), link);
+        release.source = source.map(|source| source.to_string());
+        release
     }
 
-    // checks if there is another page to look at.
-    match json["next"].as_str() {
-        Some(next) => Ok(Some(next.to_string())),
-        None => Ok(None),
+    fn find<'a>(releases : &'a HashSet<Release>, link : &str) -> Option<&'a Release> {
+        releases.iter().find(|release| release.link == link)
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn merge_marks_missing_releases_stale() {
+        let found = vec![release("11.3", "https://example.com/love-11.3-win64.zip", Some(GITHUB))];
+        let previous = vec![
+            release("11.3", "https://example.com/love-11.3-win64.zip", Some(GITHUB)),
+            release("11.2", "https://example.com/love-11.2-win64.zip", Some(GITHUB)),
+        ];
+        let links = vec![GITHUB.to_string()];
+
+        let merged = merge_releases(found, previous, &links);
+
+        assert_eq!(merged.len(), 2);
+        assert!(!find(&merged, "https://example.com/love-11.3-win64.zip").unwrap().stale);
+        assert!(find(&merged, "https://example.com/love-11.2-win64.zip").unwrap().stale);
+    }
+
+    #[test]
+    fn merge_keeps_releases_of_failed_source() {
+        let found = vec![release("11.3", "https://example.com/love-11.3-win64.zip", Some(GITHUB))];
+        let previous = vec![release("0.10.2", "https://bitbucket.org/love-0.10.2-win64.zip", Some(BITBUCKET))];
+        let links = vec![GITHUB.to_string(), BITBUCKET.to_string()];
+
+        let merged = merge_releases(found, previous, &links);
+
+        assert!(find(&merged, "https://bitbucket.org/love-0.10.2-win64.zip").unwrap().stale);
+    }
+
+    #[test]
+    fn merge_drops_removed_links() {
+        let previous = vec![release("0.10.2", "https://bitbucket.org/love-0.10.2-win64.zip", Some(BITBUCKET))];
+        let links = vec![GITHUB.to_string()];
+
+        assert!(merge_releases(Vec::new(), previous, &links).is_empty());
+    }
+
+    #[test]
+    fn merge_drops_unsourced_releases() {
+        let found = vec![release("11.3", "https://example.com/love-11.3-win64.zip", Some(GITHUB))];
+        let previous = vec![
+            release("11.3", "https://example.com/love-11.3-win64.zip", None),
+            release("0.9.0", "https://example.com/love-0.9.0-win64.zip", None),
+        ];
+        let links = vec![GITHUB.to_string()];
+
+        let merged = merge_releases(found, previous, &links);
+
+        assert_eq!(merged.len(), 1);
+        assert_eq!(find(&merged, "https://example.com/love-11.3-win64.zip").unwrap().source, Some(GITHUB.to_string()));
+    }
+
+    #[test]
+    fn migrates_schema_zero() {
+        // how releases were saved before the schema was versioned
+        #[derive(Serialize)]
+        struct OldRelease { version : Version, platform : Platform, link : String }
+        #[derive(Serialize)]
+        struct OldRepo { releases : Vec<OldRelease> }
+
+        let old = OldRepo { releases : vec![
+            OldRelease { version : Version::from_str("11.3").unwrap(), platform : Platform::Win64, link : "https://example.com/love-11.3-win64.zip".to_string() },
+        ] };
+        let path = temp_folder("repo-migrate").join(REPO_FILE);
+        File::create(&path).unwrap().write_all(toml::to_string(&old).unwrap().as_bytes()).unwrap();
+
+        let mut repo = read_repo_file(&path).unwrap();
+        assert_eq!(repo.schema, 0);
+        repo.migrate();
+
+        assert_eq!(repo.schema, SCHEMA_VERSION);
+        assert_eq!(repo.releases.len(), 1);
+        assert_eq!(repo.releases[0].format, Some("zip".to_string()));
+        assert_eq!(repo.releases[0].source, None);
+        assert!(!repo.releases[0].stale);
+    }
+
+    #[test]
+    fn refuses_newer_schema() {
+        let path = temp_folder("repo-newer").join(REPO_FILE);
+        File::create(&path).unwrap().write_all(format!("schema = {}\n",SCHEMA_VERSION + 1).as_bytes()).unwrap();
+
+        match read_repo_file(&path) {
+            Err(Error::RepoSchema { schema, .. }) => assert_eq!(schema, SCHEMA_VERSION + 1),
+            _ => panic!("expected a schema error"),
+        }
+    }
+}
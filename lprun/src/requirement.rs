@@ -0,0 +1,254 @@
+//! version requirements, so a range of versions can be asked for instead
+//! of an exact one.
+//! 
+//! supports what you would expect from semver style requirements
+//! - `11.3` or `=11.3` exactly that version
+//! - `^11.3` anything compatible, so `>=11.3, <12`
+//! - `~0.10` only patch changes, so `>=0.10, <0.11`
+//! - `>=11`, `>11`, `<=11`, `<12` comparisons
+//! - `>=11, <12` all comma separated requirements must match
+//! - `latest` or `*` any version
+
+use error::Error;
+
+use version_lp::Version;
+
+use std::fmt;
+use std::str::FromStr;
+use std::cmp::Ordering;
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+enum Op {
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+    Caret,
+    Tilde,
+}
+
+#[derive(Clone,Debug,PartialEq)]
+struct Comparator {
+    op : Op,
+    parts : Vec<u32>,
+}
+
+/// A requirement that a version needs to match
+#[derive(Clone,Debug,PartialEq)]
+pub struct Requirement {
+    text : String,
+    comparators : Vec<Comparator>,
+}
+
+impl Requirement {
+    pub fn latest() -> Requirement {
+        //! matches any version, so the newest will be picked.
+        
+        Requirement { text : "latest".to_string(), comparators : Vec::new() }
+    }
+
+    pub fn exact(version : &Version) -> Requirement {
+        //! only matches the given version
+        
+        Requirement {
+            text : version.to_string(),
+            comparators : vec![Comparator { op : Op::Exact, parts : parts_of(version) }],
+        }
+    }
+
+    pub fn is_exact(&self) -> bool {
+        //! if this can only ever match one version
+        
+        self.comparators.len() == 1 && self.comparators[0].op == Op::Exact
+    }
+
+    pub fn matches(&self, version : &Version) -> bool {
+        //! checks if the version meets every part of the requirement.
+        
+        let version = parts_of(version);
+        self.comparators.iter().all(|comparator| comparator.matches(&version))
+    }
+}
+
+impl FromStr for Requirement {
+    type Err = Error;
+
+    fn from_str(text : &str) -> Result<Requirement,Error> {
+        let text = text.trim();
+        
+        if text.is_empty() {
+            return Err(Error::InvalidRequirement { requirement : text.to_string() });
+        }
+
+        if text == "latest" || text == "*" {
+            return Ok(Requirement::latest());
+        }
+
+        let mut comparators : Vec<Comparator> = Vec::new();
+        for section in text.split(',') {
+            comparators.push(Comparator::from_str(section.trim())
+                .map_err(|_| Error::InvalidRequirement { requirement : text.to_string() })?);
+        }
+
+        Ok(Requirement { text : text.to_string(), comparators })
+    }
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"{}",self.text)
+    }
+}
+
+impl Comparator {
+    fn matches(&self, version : &[u32]) -> bool {
+        let ordering = compare(version, &self.parts);
+
+        match self.op {
+            Op::Exact => ordering == Ordering::Equal,
+            Op::Greater => ordering == Ordering::Greater,
+            Op::GreaterEq => ordering != Ordering::Less,
+            Op::Less => ordering == Ordering::Less,
+            Op::LessEq => ordering != Ordering::Greater,
+            Op::Caret | Op::Tilde => {
+                ordering != Ordering::Less && compare(version, &self.upper_bound()) == Ordering::Less
+            },
+        }
+    }
+
+    fn upper_bound(&self) -> Vec<u32> {
+        //! the first version that doesn't match a `^` or `~` requirement.
+        
+        // the index of the part that gets bumped
+        let index = match self.op {
+            // the first non zero part, unless it is the last part given (`^0.0` is `<0.1`).
+            Op::Caret => {
+                let mut index = 0;
+                while index + 1 < self.parts.len() && self.parts[index] == 0 { index += 1; }
+                index
+            },
+            // the minor version, unless only the major is given (`~11` is `<12`).
+            _ => if self.parts.len() > 1 { 1 } else { 0 },
+        };
+
+        let mut bound : Vec<u32> = self.parts[..index].to_vec();
+        bound.push(self.parts[index] + 1);
+        bound
+    }
+}
+
+impl FromStr for Comparator {
+    type Err = ();
+
+    fn from_str(text : &str) -> Result<Comparator,()> {
+        let (op, rest) = if text.starts_with(">=") { (Op::GreaterEq, &text[2..]) }
+            else if text.starts_with("<=") { (Op::LessEq, &text[2..]) }
+            else if text.starts_with('>') { (Op::Greater, &text[1..]) }
+            else if text.starts_with('<') { (Op::Less, &text[1..]) }
+            else if text.starts_with('=') { (Op::Exact, &text[1..]) }
+            else if text.starts_with('^') { (Op::Caret, &text[1..]) }
+            else if text.starts_with('~') { (Op::Tilde, &text[1..]) }
+            else { (Op::Exact, text) };
+
+        let mut parts : Vec<u32> = Vec::new();
+        for part in rest.trim().split('.') {
+            match part.parse::<u32>() {
+                Ok(number) => parts.push(number),
+                Err(_) => return Err(()),
+            }
+        }
+
+        Ok(Comparator { op, parts })
+    }
+}
+
+fn parts_of(version : &Version) -> Vec<u32> {
+    //! the numbers that make up a version, `0.10.2` is `[0,10,2]`
+    
+    version.to_string()
+        .split('.')
+        .filter_map(|part| part.parse::<u32>().ok())
+        .collect()
+}
+
+fn compare(a : &[u32], b : &[u32]) -> Ordering {
+    //! compares two versions, missing parts count as zero so `11` is the same as `11.0.0`
+    
+    let length = if a.len() > b.len() { a.len() } else { b.len() };
+    
+    for i in 0 .. length {
+        let ordering = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    Ordering::Equal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(requirement : &str, version : &str) -> bool {
+        let requirement : Requirement = requirement.parse().unwrap();
+        requirement.matches(&Version::from_str(version).unwrap())
+    }
+
+    #[test]
+    fn exact() {
+        assert!(matches("11.3", "11.3"));
+        assert!(matches("=11.3", "11.3.0"));
+        assert!(!matches("11.3", "11.2"));
+        assert!("11.3".parse::<Requirement>().unwrap().is_exact());
+        assert!(!"^11.3".parse::<Requirement>().unwrap().is_exact());
+    }
+
+    #[test]
+    fn caret_zero_major() {
+        assert!(matches("^0.10", "0.10.2"));
+        assert!(!matches("^0.10", "0.11.0"));
+        assert!(!matches("^0.10", "0.9.2"));
+        assert!(matches("^0.0", "0.0.5"));
+        assert!(!matches("^0.0", "0.1.0"));
+    }
+
+    #[test]
+    fn caret() {
+        assert!(matches("^11.1", "11.3"));
+        assert!(!matches("^11.1", "11.0"));
+        assert!(!matches("^11.1", "12.0"));
+    }
+
+    #[test]
+    fn tilde() {
+        assert!(matches("~11", "11.3"));
+        assert!(!matches("~11", "12.0"));
+        assert!(matches("~0.10", "0.10.2"));
+        assert!(!matches("~0.10", "0.11.0"));
+    }
+
+    #[test]
+    fn comma_joined_ranges() {
+        assert!(matches(">=0.10, <11", "0.10.2"));
+        assert!(!matches(">=0.10, <11", "11.0"));
+        assert!(!matches(">=0.10, <11", "0.9.2"));
+        assert!(matches(">0.9,<=11.3", "11.3"));
+    }
+
+    #[test]
+    fn latest() {
+        assert!(matches("latest", "0.8.0"));
+        assert!(matches("*", "11.3"));
+        assert_eq!("latest".parse::<Requirement>().unwrap(), Requirement::latest());
+    }
+
+    #[test]
+    fn invalid() {
+        assert!("".parse::<Requirement>().is_err());
+        assert!("eleven".parse::<Requirement>().is_err());
+        assert!(">=11,".parse::<Requirement>().is_err());
+        assert!("^11.x".parse::<Requirement>().is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use error::Error;
+
+use platform_lp::Platform;
+use version_lp::Version;
+
+use std::fmt;
+
+use smart_hash::traits::SmartHashSet;
+
+use requirement::Requirement;
+use structs::release::Release;
+use binary;
+use repo;
+
+/// The concrete version picked for a `Requirement`, and why it was picked.
+pub struct Resolution {
+    pub platform : Platform,
+    pub version : Version,
+    pub requirement : Requirement,
+    /// if it was picked from the installed versions, otherwise it came from the repo.
+    pub installed : bool,
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let from = if self.installed { "installed" } else { "available" };
+        
+        if self.requirement.is_exact() {
+            write!(f,"LOVE {} for {}, {}",self.version,self.platform,from)
+        } else {
+            write!(f,"LOVE {} for {}, newest {} version matching '{}'",self.version,self.platform,from,self.requirement)
+        }
+    }
+}
+
+pub fn resolve(platform : &Platform, requirement : &Requirement) -> Result<Resolution,Error> {
+    //! picks the version to use for the requirement
+    //! 
+    //! uses the newest matching installed version, and if none are installed it then
+    //! uses the newest matching version in the local repo.
+    
+    let installed = binary::get_installed()?;
+    if let Some(version) = newest_matching(get_matching!(installed,platform == platform.clone()), requirement) {
+        return Ok(Resolution { 
+            platform : platform.clone(), 
+            version, 
+            requirement : requirement.clone(), 
+            installed : true 
+        });
+    }
+
+    let available = repo::load_local_repo()?;
+    if let Some(version) = newest_matching(get_matching!(available,platform == platform.clone()), requirement) {
+        return Ok(Resolution { 
+            platform : platform.clone(), 
+            version, 
+            requirement : requirement.clone(), 
+            installed : false 
+        });
+    }
+
+    Err(Error::VersionNotFound { platform : platform.to_string(), version : requirement.to_string() })
+}
+
+fn newest_matching(releases : Option<Vec<&Release>>, requirement : &Requirement) -> Option<Version> {
+    //! the newest version out of the releases that matches the requirement.
+    //! 
+    //! prereleases are skipped unless the requirement is for that exact version.
+
+    let mut versions : Vec<Version> = Vec::new();
+    if let Some(releases) = releases {
+        for release in releases {
+            if release.prerelease && !requirement.is_exact() { continue; }
+            if requirement.matches(&release.version) {
+                versions.push(release.version.clone());
+            }
+        }
+    }
+
+    versions.sort();
+    versions.pop()
+}
@@ -0,0 +1,18 @@
+//! helpers for reading lpsettings values that aren't simple text or switches.
+
+use lpsettings;
+
+use std::str::FromStr;
+
+pub fn get_number<T : FromStr + ToString>(key : &str, default : T) -> T {
+    //! reads a number setting, using the default if it isn't set or isn't a number
+    
+    let value = lpsettings::get_value_or(key, &default.to_string()).to_string();
+    match value.trim().parse::<T>() {
+        Ok(number) => number,
+        Err(_) => {
+            error!("Setting '{}' should be a number, not '{}'",key,value);
+            default
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use error::Error;
+
+use reqwest;
+use reqwest::StatusCode;
+use serde_json;
+
+use structs::release::Release;
+use sources::{self, Page, ReleaseSource, Validators};
+
+/// Bitbucket repository downloads, using the 2.0 api
+pub struct Bitbucket {
+    url : String,
+    client : reqwest::Client,
+}
+
+impl Bitbucket {
+    pub fn new(url : &str, client : &reqwest::Client) -> Bitbucket {
+        Bitbucket { url : url.to_string(), client : client.clone() }
+    }
+}
+
+impl ReleaseSource for Bitbucket {
+    fn fetch_page(&self, cursor : Option<&str>, cached : &Validators) -> Result<Option<Page>,Error> {
+        //! gets links from bitbucket repos, the cursor is the `next` link
+        //! bitbucket gives with each page.
+        
+        let url = cursor.unwrap_or(&self.url);
+        let mut releases : Vec<Release> = Vec::new();
+
+        let mut resp = sources::conditional(self.client.get(url), cached)
+            .send()
+            .and_then(|resp| resp.error_for_status())
+            .map_err(|error| Error::network(url, error))?;
+
+        if resp.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+
+        let validators = sources::validators(&resp);
+        let raw_json = resp.text().map_err(|error| Error::network(url, error))?;
+        let json : serde_json::Value = serde_json::from_str(&raw_json)
+            .map_err(|error| Error::repo_parse(url, error))?;
+
+        if let Some(json_releases) = json["values"].as_array() {
+            for download in json_releases {
+                if let (Some(name), Some(link)) = (download["name"].as_str(), download["links"]["self"]["href"].as_str()) {
+                    if let Some(mut release) = sources::classify(name, link) {
+                        release.size = download["size"].as_u64();
+                        release.published = download["created_on"].as_str().map(|date| date.to_string());
+                        info!("Found release {}",release);
+                        releases.push(release);
+                    }
+                }
+            }
+        }
+
+        // checks if there is another page to look at.
+        let next = match json["next"].as_str() {
+            Some(next) => Some(next.to_string()),
+            None => None,
+        };
+
+        Ok(Some(Page { releases, next, validators }))
+    }
+}
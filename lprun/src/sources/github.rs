@@ -0,0 +1,205 @@
+use error::Error;
+
+use reqwest;
+use reqwest::StatusCode;
+use reqwest::header::{ACCEPT, LINK};
+use serde_json;
+
+use structs::release::Release;
+use sources::{self, Page, ReleaseSource, Validators};
+
+/// GitHub releases, using the `/repos/{owner}/{repo}/releases` api
+/// 
+/// every asset of every release is checked, and the link used is the
+/// asset's `browser_download_url`.
+pub struct Github {
+    url : String,
+    client : reqwest::Client,
+}
+
+impl Github {
+    pub fn new(url : &str, client : &reqwest::Client) -> Github {
+        Github { url : url.to_string(), client : client.clone() }
+    }
+}
+
+impl ReleaseSource for Github {
+    fn fetch_page(&self, cursor : Option<&str>, cached : &Validators) -> Result<Option<Page>,Error> {
+        //! gets links from github releases, the cursor is the `next` link
+        //! from the `Link` header github gives with each page.
+
+        let url = cursor.unwrap_or(&self.url);
+        let mut releases : Vec<Release> = Vec::new();
+
+        // a conditional request that isn't modified doesn't count against the rate limit.
+        let request = self.client
+            .get(url)
+            .header(ACCEPT, "application/vnd.github.v3+json");
+        let mut resp = sources::conditional(request, cached)
+            .send()
+            .and_then(|resp| resp.error_for_status())
+            .map_err(|error| Error::network(url, error))?;
+
+        if resp.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+
+        let validators = sources::validators(&resp);
+
+        let next = match resp.headers().get(LINK) {
+            None => None,
+            Some(header) => next_link(header.to_str().map_err(|error| Error::repo_parse(url, error))?),
+        };
+
+        let raw_json = resp.text().map_err(|error| Error::network(url, error))?;
+        let json : serde_json::Value = serde_json::from_str(&raw_json)
+            .map_err(|error| Error::repo_parse(url, error))?;
+
+        if let Some(json_releases) = json.as_array() {
+            for json_release in json_releases {
+                if let Some(assets) = json_release["assets"].as_array() {
+                    for asset in assets {
+                        if let (Some(name), Some(link)) = (asset["name"].as_str(), asset["browser_download_url"].as_str()) {
+                            if let Some(mut release) = sources::classify(name, link) {
+                                // newer versions of the api include the asset's checksum
+                                if let Some(digest) = asset["digest"].as_str() {
+                                    if digest.starts_with("sha256:") {
+                                        release.checksum = Some(digest.to_string());
+                                    }
+                                }
+                                release.size = asset["size"].as_u64();
+                                release.published = json_release["published_at"].as_str().map(|date| date.to_string());
+                                release.prerelease = json_release["prerelease"].as_bool().unwrap_or(false);
+                                info!("Found release {}",release);
+                                releases.push(release);
+                            }
+                        }
+                    }
+                }
+            }
+        } else {
+            return Err(Error::repo_parse(url, format_err!("expected a list of releases")));
+        }
+
+        Ok(Some(Page { releases, next, validators }))
+    }
+}
+
+fn next_link(header : &str) -> Option<String> {
+    //! finds the `rel="next"` link in a `Link` header, which looks like
+    //! `<https://api.github.com/...?page=2>; rel="next", <...>; rel="last"`
+    
+    for part in header.split(',') {
+        let mut sections = part.split(';');
+        let link = match sections.next() {
+            None => continue,
+            Some(link) => link.trim(),
+        };
+
+        for param in sections {
+            let param = param.trim().replace(" ", "");
+            if param == "rel=\"next\"" || param == "rel=next" {
+                if link.starts_with('<') && link.ends_with('>') {
+                    return Some(link[1..link.len()-1].to_string());
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::{serve, response};
+
+    use platform_lp::Platform;
+
+    static PAGE_ONE : &str = r#"[
+        { "tag_name" : "11.3", "published_at" : "2019-12-01T00:00:00Z", "prerelease" : false, "assets" : [
+            { "name" : "love-11.3-win64.zip", "size" : 1234, "digest" : "sha256:abcd",
+              "browser_download_url" : "https://example.com/love-11.3-win64.zip" },
+            { "name" : "love-11.3-source.txt", "size" : 10,
+              "browser_download_url" : "https://example.com/notes.txt" }
+        ] }
+    ]"#;
+
+    static PAGE_TWO : &str = r#"[
+        { "tag_name" : "11.2", "prerelease" : true, "assets" : [
+            { "name" : "love-11.2-win32.zip", "browser_download_url" : "https://example.com/love-11.2-win32.zip" }
+        ] }
+    ]"#;
+
+    fn host(request : &str) -> String {
+        request.lines()
+            .find(|line| line.to_lowercase().starts_with("host:"))
+            .map(|line| line[5..].trim().to_string())
+            .unwrap()
+    }
+
+    #[test]
+    fn next_link_finds_next() {
+        let header = r#"<https://api.github.com/repositories/1/releases?page=2>; rel="next", <https://api.github.com/repositories/1/releases?page=5>; rel="last""#;
+        assert_eq!(next_link(header), Some("https://api.github.com/repositories/1/releases?page=2".to_string()));
+    }
+
+    #[test]
+    fn next_link_without_next() {
+        let header = r#"<https://api.github.com/repositories/1/releases?page=1>; rel="first", <https://api.github.com/repositories/1/releases?page=4>; rel="prev""#;
+        assert_eq!(next_link(header), None);
+        assert_eq!(next_link(""), None);
+    }
+
+    #[test]
+    fn next_link_unquoted_and_spaced() {
+        assert_eq!(next_link("<http://localhost/2>;rel=next"), Some("http://localhost/2".to_string()));
+        assert_eq!(next_link(r#"<http://localhost/3> ; rel = "next""#), Some("http://localhost/3".to_string()));
+    }
+
+    #[test]
+    fn fetches_canned_pages() {
+        let (url, server) = serve(2, |number, request| {
+            if number == 0 {
+                let next = format!("<http://{}/repos/love2d/love/releases?page=2>; rel=\"next\"",host(request));
+                response("200 OK", &[("Content-Length", PAGE_ONE.len().to_string()), ("Link", next)], PAGE_ONE.as_bytes())
+            } else {
+                response("200 OK", &[("Content-Length", PAGE_TWO.len().to_string())], PAGE_TWO.as_bytes())
+            }
+        });
+
+        let source = sources::from_link(&format!("github+{}/repos/love2d/love/releases",url), &reqwest::Client::new()).unwrap();
+
+        let first = source.fetch_page(None, &Validators::default()).unwrap().unwrap();
+        assert_eq!(first.releases.len(), 1);
+        assert!(first.releases[0].platform == Platform::Win64);
+        assert_eq!(first.releases[0].link, "https://example.com/love-11.3-win64.zip");
+        assert_eq!(first.releases[0].checksum, Some("sha256:abcd".to_string()));
+        assert_eq!(first.releases[0].size, Some(1234));
+        assert!(!first.releases[0].prerelease);
+
+        let next = first.next.unwrap();
+        assert!(next.ends_with("/repos/love2d/love/releases?page=2"));
+
+        let second = source.fetch_page(Some(&next), &Validators::default()).unwrap().unwrap();
+        assert_eq!(second.releases.len(), 1);
+        assert!(second.releases[0].platform == Platform::Win32);
+        assert!(second.releases[0].prerelease);
+        assert!(second.next.is_none());
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("GET /repos/love2d/love/releases HTTP/1.1"));
+        assert!(requests[0].to_lowercase().contains("accept: application/vnd.github.v3+json"));
+    }
+
+    #[test]
+    fn not_modified_page() {
+        let (url, server) = serve(1, |_, _| response("304 Not Modified", &[], &[]));
+
+        let source = sources::from_link(&format!("github+{}/repos/love2d/love/releases",url), &reqwest::Client::new()).unwrap();
+        let cached = Validators { etag : Some("\"abc\"".to_string()), last_modified : None };
+
+        assert!(source.fetch_page(None, &cached).unwrap().is_none());
+        assert!(server.join().unwrap()[0].to_lowercase().contains("if-none-match: \"abc\""));
+    }
+}
@@ -0,0 +1,153 @@
+use error::Error;
+
+use reqwest;
+use reqwest::{StatusCode, Url};
+use serde_json;
+use toml;
+
+use platform_lp::Platform;
+use version_lp::Version;
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use structs::release::Release;
+use sources::{self, Page, ReleaseSource, Validators};
+
+/// A static index of releases, for mirrors on any web server or file share.
+/// 
+/// the index is a json (or toml, if the link ends with `.toml`) list of
+/// releases, see the readme for the format. urls in it can be relative to 
+/// the index itself.
+pub struct Index {
+    url : String,
+    client : reqwest::Client,
+}
+
+/// A release as it is written in an index.
+#[derive(Serialize,Deserialize)]
+pub struct IndexEntry {
+    pub version : String,
+    pub platform : String,
+    pub url : String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256 : Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size : Option<u64>,
+}
+
+/// The whole index, toml can't have a list at the top so the releases
+/// are always under `releases` in it. json can be either.
+#[derive(Serialize,Deserialize)]
+pub struct IndexFile {
+    pub releases : Vec<IndexEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonIndex {
+    List(Vec<IndexEntry>),
+    File(IndexFile),
+}
+
+impl Index {
+    pub fn new(url : &str, client : &reqwest::Client) -> Index {
+        Index { url : url.to_string(), client : client.clone() }
+    }
+
+    pub fn is_index(url : &str) -> bool {
+        //! if the link looks like an index file, from its extension
+        
+        let lower_url = url.to_lowercase();
+        let path = lower_url.split(|c| c == '?' || c == '#').next().unwrap_or("");
+        path.ends_with(".json") || path.ends_with(".toml")
+    }
+
+    fn location(&self) -> Result<Url,Error> {
+        //! the url of the index, which can also just be a path to a file.
+        
+        if let Ok(url) = Url::parse(&self.url) {
+            // windows paths like `C:\\love` parse with `c` as the scheme
+            if url.scheme().len() > 1 { return Ok(url); }
+        }
+
+        Path::new(&self.url).canonicalize().ok()
+            .and_then(|path| Url::from_file_path(path).ok())
+            .ok_or_else(|| Error::repo_parse(&self.url, format_err!("not a url or a file that exists")))
+    }
+}
+
+impl ReleaseSource for Index {
+    fn fetch_page(&self, _cursor : Option<&str>, cached : &Validators) -> Result<Option<Page>,Error> {
+        //! the whole index is one page. local index files are always read again.
+        
+        let location = self.location()?;
+        let url = location.as_str();
+
+        let (raw, validators) = if location.scheme() == "file" {
+            let path = location.to_file_path()
+                .map_err(|_| Error::repo_parse(url, format_err!("not a valid file url")))?;
+            let mut buffer = String::new();
+            File::open(&path)
+                .and_then(|mut file| file.read_to_string(&mut buffer))
+                .map_err(|error| Error::repo_parse(url, error))?;
+            (buffer, Validators::default())
+        } else {
+            let mut resp = sources::conditional(self.client.get(url), cached)
+                .send()
+                .and_then(|resp| resp.error_for_status())
+                .map_err(|error| Error::network(url, error))?;
+
+            if resp.status() == StatusCode::NOT_MODIFIED {
+                return Ok(None);
+            }
+
+            let validators = sources::validators(&resp);
+            (resp.text().map_err(|error| Error::network(url, error))?, validators)
+        };
+
+        let entries = if location.path().to_lowercase().ends_with(".toml") {
+            let index : IndexFile = toml::from_str(&raw).map_err(|error| Error::repo_parse(url, error))?;
+            index.releases
+        } else {
+            match serde_json::from_str(&raw).map_err(|error| Error::repo_parse(url, error))? {
+                JsonIndex::List(releases) => releases,
+                JsonIndex::File(index) => index.releases,
+            }
+        };
+
+        let mut releases : Vec<Release> = Vec::new();
+        for entry in entries {
+            match to_release(&location, entry) {
+                Ok(release) => {
+                    info!("Found release {}",release);
+                    releases.push(release);
+                },
+                Err(error) => error!("Skipping release in '{}': {}",url,error),
+            }
+        }
+
+        Ok(Some(Page { releases, next : None, validators }))
+    }
+}
+
+fn to_release(location : &Url, entry : IndexEntry) -> Result<Release,Error> {
+    //! makes the release from the entry, the entry's url is relative to the index.
+    
+    let version = Version::from_str(&entry.version)
+        .ok_or_else(|| Error::repo_parse(location.as_str(), format_err!("'{}' isn't a version",entry.version)))?;
+    let platform = Platform::new(&entry.platform);
+    if platform == Platform::None {
+        return Err(Error::repo_parse(location.as_str(), format_err!("'{}' isn't a platform",entry.platform)));
+    }
+    let link = location.join(&entry.url)
+        .map_err(|error| Error::repo_parse(&entry.url, error))?
+        .to_string();
+
+    let mut release = Release::new(platform, version, &link);
+    release.checksum = entry.sha256;
+    release.size = entry.size;
+    release.format = sources::archive_format(&link);
+    Ok(release)
+}
@@ -0,0 +1,75 @@
+use error::Error;
+
+use reqwest::Url;
+
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+
+use structs::release::Release;
+use sources::{self, Page, ReleaseSource, Validators};
+
+/// A folder of release archives, like a mirror on a file share.
+/// 
+/// every file in the folder (and the folders in it) that looks like a LOVE
+/// release is used, and its link is a `file://` url to it so installing 
+/// copies it instead of downloading.
+pub struct Local {
+    path : PathBuf,
+}
+
+impl Local {
+    pub fn new<P : AsRef<Path>>(path : P) -> Local {
+        Local { path : path.as_ref().to_path_buf() }
+    }
+}
+
+impl ReleaseSource for Local {
+    fn fetch_page(&self, _cursor : Option<&str>, _cached : &Validators) -> Result<Option<Page>,Error> {
+        //! everything is on one page, and the folder is always looked through 
+        //! again because that is cheap.
+        
+        let folder = self.path.canonicalize()
+            .map_err(|error| Error::repo_parse(&self.path.display().to_string(), error))?;
+        let mut releases : Vec<Release> = Vec::new();
+        scan(&folder, &mut releases)?;
+
+        Ok(Some(Page { releases, next : None, validators : Validators::default() }))
+    }
+}
+
+fn scan(folder : &Path, releases : &mut Vec<Release>) -> Result<(),Error> {
+    //! adds the releases in the folder, and the folders inside of it.
+    
+    let location = folder.display().to_string();
+
+    for entry in read_dir(folder).map_err(|error| Error::repo_parse(&location, error))? {
+        let entry = entry.map_err(|error| Error::repo_parse(&location, error))?;
+        let path = entry.path();
+        // doesn't follow links to folders, they can loop back to a parent.
+        let is_dir = entry.file_type().map_err(|error| Error::repo_parse(&location, error))?.is_dir();
+        if is_dir {
+            scan(&path, releases)?;
+            continue;
+        }
+
+        let name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        let link = match Url::from_file_path(&path) {
+            Ok(url) => url.to_string(),
+            Err(_) => continue,
+        };
+
+        // only archives, so things like checksum files next to them are skipped.
+        if sources::archive_format(&link).is_none() { continue; }
+
+        if let Some(mut release) = sources::classify(&name, &link) {
+            release.size = path.metadata().ok().map(|meta| meta.len());
+            info!("Found release {}",release);
+            releases.push(release);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,257 @@
+//! release sources, the different places LOVE releases are published.
+//!
+//! each link in the repo list is turned into a `ReleaseSource`, which is
+//! then paged through by `repo::update_local_repo`. a link can force which
+//! source to use by prefixing it with the source name, like
+//! `bitbucket+https://api.bitbucket.org/2.0/repositories/rude/love/downloads`,
+//! otherwise the source is guessed from the link itself.
+//! 
+//! a folder of archives can be used with a `file://` url, or with `local+` 
+//! and the path to the folder. links to `.json` or `.toml` files (or any link
+//! with `index+`) are read as a static index of releases.
+
+use error::Error;
+
+use platform_lp::{PartialPlatform, Platform};
+use version_lp::Version;
+use regex::Regex;
+use reqwest;
+use reqwest::Url;
+use reqwest::header::{HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+
+use structs::release::Release;
+
+mod bitbucket;
+mod github;
+mod index;
+mod local;
+
+pub use self::bitbucket::Bitbucket;
+pub use self::github::Github;
+pub use self::index::{Index, IndexEntry, IndexFile};
+pub use self::local::Local;
+
+use std::path::Path;
+
+// linux is the only one that can resolve without getting a full match
+// on platform, these should only be lowercase!
+static VALID_EXT_LINUX : [&str;3] = [ "appimage","tar.gz","tar.xz" ];
+static VALID_EXT_WINDOWS : [&str;1] = [ "zip" ];
+static VALID_EXT_MAC : [&str;2] = [ "zip","dmg" ];
+
+static REGEX_VERSION_MATCH : &str = r"(\d+[-|.|_]\d+[[-|.|_]\d+]*)";
+
+/// A single page of results from a `ReleaseSource`
+pub struct Page {
+    pub releases : Vec<Release>,
+    /// the cursor to give `fetch_page` to get the next page, `None` if this was the last.
+    pub next : Option<String>,
+    /// what identifies this version of the page, to ask later if it has changed.
+    pub validators : Validators,
+}
+
+/// The `ETag` and `Last-Modified` a page was served with.
+#[derive(Clone,Default)]
+pub struct Validators {
+    pub etag : Option<String>,
+    pub last_modified : Option<String>,
+}
+
+/// Somewhere that LOVE releases can be discovered from.
+pub trait ReleaseSource {
+    /// fetches a page of releases, `None` gets the first page and the page's
+    /// `next` cursor is used to get the following one.
+    /// 
+    /// the request is conditional on the `cached` validators, and gives `None`
+    /// back if the page hasn't changed since then.
+    fn fetch_page(&self, cursor : Option<&str>, cached : &Validators) -> Result<Option<Page>,Error>;
+}
+
+pub fn from_link(link : &str, client : &reqwest::Client) -> Option<Box<dyn ReleaseSource>> {
+    //! creates the source for a repo link, `None` if nothing knows how to read it.
+    //! 
+    //! sources use the `client` for all their requests.
+    
+    let (kind, url) = split_kind(link);
+
+    match kind {
+        Some("bitbucket") => Some(Box::new(Bitbucket::new(url, client))),
+        Some("github") => Some(Box::new(Github::new(url, client))),
+        Some("local") => Some(Box::new(Local::new(url))),
+        Some("index") => Some(Box::new(Index::new(url, client))),
+        Some(kind) => {
+            error!("Unknown release source type '{}' for '{}'",kind,url);
+            None
+        },
+        None => {
+            if url.contains("bitbucket") { 
+                Some(Box::new(Bitbucket::new(url, client)))
+            } else if url.contains("api.github.com") {
+                Some(Box::new(Github::new(url, client)))
+            } else if Index::is_index(url) {
+                Some(Box::new(Index::new(url, client)))
+            } else if url.starts_with("file://") {
+                match Url::parse(url).ok().and_then(|url| url.to_file_path().ok()) {
+                    Some(path) => Some(Box::new(Local::new(path))),
+                    None => {
+                        error!("'{}' isn't a valid file url",url);
+                        None
+                    },
+                }
+            } else if Path::new(url).is_dir() {
+                Some(Box::new(Local::new(url)))
+            } else {
+                None
+            }
+        },
+    }
+}
+
+pub fn conditional(request : reqwest::RequestBuilder, cached : &Validators) -> reqwest::RequestBuilder {
+    //! makes the request only send back the page if it changed since it had the `cached` validators.
+
+    let mut request = request;
+    if let Some(ref etag) = cached.etag {
+        request = request.header(IF_NONE_MATCH, etag.as_str());
+    }
+    if let Some(ref last_modified) = cached.last_modified {
+        request = request.header(IF_MODIFIED_SINCE, last_modified.as_str());
+    }
+    request
+}
+
+pub fn validators(resp : &reqwest::Response) -> Validators {
+    //! gets the validators the response was served with.
+    
+    let header = |name : HeaderName| resp.headers().get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string());
+
+    Validators {
+        etag : header(ETAG),
+        last_modified : header(LAST_MODIFIED),
+    }
+}
+
+fn split_kind(link : &str) -> (Option<&str>,&str) {
+    //! splits the optional `kind+` prefix off of a link.
+
+    if let Some(position) = link.find('+') {
+        let (kind, rest) = link.split_at(position);
+        // only counts as a kind if it is a simple word, so links with a `+` 
+        // somewhere in the path don't get split.
+        if !kind.is_empty() && kind.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return (Some(kind), &rest[1..]);
+        }
+    }
+
+    (None, link)
+}
+
+pub fn extension_rank(release : &Release) -> usize {
+    //! how much the release's file type is preferred, lower is better. the
+    //! order is the same as the `VALID_EXT_*` lists, so a mac `.zip` is picked 
+    //! over a `.dmg` when both are available.
+    
+    let lower_link = release.link.to_lowercase();
+    let extensions : &[&str] = if release.platform == PartialPlatform::Mac { &VALID_EXT_MAC }
+        else if release.platform == PartialPlatform::Windows { &VALID_EXT_WINDOWS }
+        else { &VALID_EXT_LINUX };
+
+    extensions.iter()
+        .position(|ext| lower_link.ends_with(ext))
+        .unwrap_or(extensions.len())
+}
+
+pub fn archive_format(link : &str) -> Option<String> {
+    //! the kind of file the link is, from its extension.
+    
+    let lower_link = link.to_lowercase();
+    VALID_EXT_LINUX.iter()
+        .chain(VALID_EXT_WINDOWS.iter())
+        .chain(VALID_EXT_MAC.iter())
+        .find(|ext| lower_link.ends_with(*ext))
+        .map(|ext| ext.to_string())
+}
+
+pub fn classify(name : &str, link : &str) -> Option<Release> {
+    //! makes a release from a download's file name and link, if it is a
+    //! LOVE release that we can use.
+    //! 
+    //! the version is pulled from the name, and the platform is resolved from
+    //! the name and the extension of the link.
+
+    let re_version = Regex::new(REGEX_VERSION_MATCH).unwrap();
+    
+    let version = match re_version.captures(name) {
+        None => return None,
+        Some(version_cap) => match Version::from_str(version_cap.get(1).unwrap().as_str()) {
+            Some(version) => version,
+            None => {
+                error!("Error parsing version {:?}",version_cap.get(1).unwrap());
+                return None;
+            },
+        }
+    };
+
+    // resolves the platform, does it this way because there is some nuance it it,
+    // because some of the files don't have platforms, but the extension (like AppImage)
+    // gives it away.
+    // also some releases are installable, and we don't want those, we want the 
+    // 'portable' zipped archive release instead.
+    let platform = {
+
+        let lower_link = link.to_lowercase();
+        let mut platform_guess = Platform::new(name);
+        let mut valid = false;
+
+        // checks if valid linux platform
+        if platform_guess == PartialPlatform::Linux {
+            for part in &VALID_EXT_LINUX {
+                if lower_link.contains(part) {
+                    valid = true; break;
+                }
+            }
+        } else if platform_guess == PartialPlatform::Windows {
+            for part in &VALID_EXT_WINDOWS {
+                if lower_link.contains(part) {
+                    valid = true; break;
+                }
+            }
+        } else if platform_guess == PartialPlatform::Mac {
+            for part in &VALID_EXT_MAC {
+                if lower_link.contains(part) {
+                    valid = true; break;
+                }
+            }
+        } else if platform_guess == Platform::None {
+            for part in &VALID_EXT_LINUX {
+                if lower_link.contains(part) {
+                    if lower_link.contains("686") || lower_link.contains("32") {
+                        platform_guess = Platform::Nix32;
+                        valid = true;
+                    } else if lower_link.contains("64") {
+                        platform_guess = Platform::Nix64;
+                        valid = true;
+                    }
+                    break;
+                }
+            } 
+        }
+
+        if valid {
+            platform_guess
+        } else {
+            Platform::None
+        }
+    };
+
+    if platform == Platform::None {
+        error!("Error parsing platform {}",name);
+        None
+    } else {
+        let mut release = Release::new(platform, version, link);
+        release.format = archive_format(link);
+        Some(release)
+    }
+}
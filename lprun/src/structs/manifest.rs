@@ -0,0 +1,72 @@
+use error::Error;
+
+use toml;
+
+use std::path::{Path,PathBuf};
+use std::fs::File;
+use std::io::{Read,Write};
+use std::time::{SystemTime,UNIX_EPOCH};
+
+static MANIFEST_FILE : &str = ".lprun-install.toml";
+
+/// Written into each install folder when it is installed, so we know where
+/// it came from and where the binary is instead of guessing.
+#[derive(Serialize,Deserialize)]
+pub struct InstallManifest {
+    /// where the release was downloaded from.
+    pub link : String,
+    /// the sha256 of the downloaded archive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum : Option<String>,
+    /// the binary, relative to the install folder.
+    pub executable : PathBuf,
+    /// when it was installed, in seconds since the unix epoch.
+    pub installed : u64,
+}
+
+impl InstallManifest {
+    pub fn new(link : &str, checksum : Option<String>, executable : PathBuf) -> InstallManifest {
+        let installed = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs(),
+            Err(_) => 0,
+        };
+
+        InstallManifest { link : link.to_string(), checksum, executable, installed }
+    }
+
+    pub fn load<P : AsRef<Path>>(folder : P) -> Option<InstallManifest> {
+        //! reads the manifest in the install folder, `None` if there isn't one 
+        //! (installed by an older lprun) or it can't be read.
+        
+        let path = folder.as_ref().join(MANIFEST_FILE);
+        if !path.exists() {
+            return None;
+        }
+
+        let mut buffer = String::new();
+        if let Err(error) = File::open(&path).and_then(|mut file| file.read_to_string(&mut buffer)) {
+            error!("Couldn't read '{}': {}",path.display().to_string(),error);
+            return None;
+        }
+
+        match toml::from_str(&buffer) {
+            Ok(manifest) => Some(manifest),
+            Err(error) => {
+                error!("Couldn't read '{}': {}",path.display().to_string(),error);
+                None
+            }
+        }
+    }
+
+    pub fn save<P : AsRef<Path>>(&self, folder : P) -> Result<(),Error> {
+        //! writes the manifest into the install folder.
+        
+        let path = folder.as_ref().join(MANIFEST_FILE);
+        let toml_string = toml::to_string(self)
+            .map_err(|error| Error::serialize(&path.display().to_string(), error))?;
+
+        let mut file = File::create(&path)?;
+        file.write_all(toml_string.as_bytes())?;
+        Ok(())
+    }
+}
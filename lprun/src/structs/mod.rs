@@ -1 +1,4 @@
-pub mod release;
\ No newline at end of file
+pub mod release;
+pub mod options;
+pub mod manifest;
+pub mod repo_file;
\ No newline at end of file
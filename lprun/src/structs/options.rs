@@ -0,0 +1,78 @@
+use error::Error;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Options for how LOVE is run, used with `run`
+#[derive(Default,Clone,Debug)]
+pub struct RunOptions {
+    /// returns as soon as LOVE is started instead of waiting for it to close.
+    pub detach : bool,
+    /// arguments passed to the game, after the project path.
+    pub args : Vec<String>,
+    /// additional environment variables LOVE is run with.
+    pub env : Vec<(String,String)>,
+}
+
+/// What part of an install is happening, for progress reports
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum Phase {
+    Download,
+    Verify,
+    Extract,
+}
+
+/// How far along an install is
+#[derive(Clone,Copy,Debug)]
+pub struct Progress {
+    pub phase : Phase,
+    /// bytes downloaded so far, only counts up during `Phase::Download`
+    pub done : u64,
+    /// the size of the download, if the server said.
+    pub total : Option<u64>,
+}
+
+/// Used to cancel an install from somewhere else (like another thread), 
+/// clones all cancel the same install.
+#[derive(Clone,Default,Debug)]
+pub struct CancelToken {
+    cancelled : Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Options for how LOVE is installed, used with `install_with`
+#[derive(Default)]
+pub struct InstallOptions {
+    /// called as the install progresses.
+    pub progress : Option<Box<dyn Fn(Progress) + Send + Sync>>,
+    /// stops the install (with `Error::Cancelled`) when cancelled.
+    pub cancel : Option<CancelToken>,
+}
+
+impl InstallOptions {
+    pub(crate) fn report(&self, phase : Phase, done : u64, total : Option<u64>) {
+        if let Some(ref progress) = self.progress {
+            progress(Progress { phase, done, total });
+        }
+    }
+
+    pub(crate) fn check_cancelled(&self) -> Result<(),Error> {
+        match self.cancel {
+            Some(ref token) if token.is_cancelled() => Err(Error::Cancelled),
+            _ => Ok(()),
+        }
+    }
+}
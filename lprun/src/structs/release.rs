@@ -2,14 +2,55 @@ use platform_lp::Platform;
 use version_lp::Version;
 
 use std::fmt;
-use std::collections::HashSet;
+use std::path::PathBuf;
 
-/// Internal struct to describe a LOVE release.
-#[derive(Hash,Eq,PartialEq,Serialize,Deserialize,SmartHash)]
+/// A LOVE release, a version for a platform and where to download it.
+/// 
+/// everything besides those is only known if the source published it.
+#[derive(Hash,Eq,PartialEq,Clone,Serialize,Deserialize,SmartHash)]
 pub struct Release {
     pub version : Version,
     pub platform: Platform,
     pub link : String,
+    /// the sha256 of the archive, if the source publishes one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum : Option<String>,
+    /// the repo link the release was found with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source : Option<String>,
+    /// the size of the archive in bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size : Option<u64>,
+    /// when the release was published, as the source gave it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published : Option<String>,
+    /// what kind of file the link is, like `zip` or `appimage`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format : Option<String>,
+    /// a test build, only used when its version is asked for exactly.
+    #[serde(default)]
+    pub prerelease : bool,
+    /// the release wasn't found the last time the repo was updated, either
+    /// because its source was down or it isn't there anymore.
+    #[serde(default)]
+    pub stale : bool,
+}
+
+impl Release {
+    pub fn new(platform : Platform, version : Version, link : &str) -> Release {
+        Release {
+            version,
+            platform,
+            link : link.to_string(),
+            checksum : None,
+            source : None,
+            size : None,
+            published : None,
+            format : None,
+            prerelease : false,
+            stale : false,
+        }
+    }
 }
 
 impl fmt::Display for Release {
@@ -30,36 +71,15 @@ impl std::cmp::PartialOrd for Release {
     }
 }
 
-/// created this struct because serializing and deserializing 
-/// a HashSet doesn't work.
-#[derive(Hash,Eq,PartialEq,Serialize,Deserialize)]
-pub struct ReleaseExporter {
-    pub releases : Vec<Release>,
-}
-
-impl ReleaseExporter {
-    pub fn from_release(mut releases : HashSet<Release>) -> ReleaseExporter {
-        let mut vec : Vec<Release>= Vec::new();
-
-        for release in releases.drain() {
-            vec.push(release);
-        }
-
-        ReleaseExporter {
-            releases : vec,
-        }
-    }
-
-    pub fn to_release(mut self) -> HashSet<Release> {
-        let mut hash : HashSet<Release> = HashSet::new();
-
-        loop {
-            match self.releases.pop() {
-                Some(release) => { hash.insert(release); },
-                None => { break; },
-            }
-        }
-
-        hash
-    }
+/// Information about a release for listing, like if it is installed
+/// and where it would be installed to.
+#[derive(Clone)]
+pub struct ReleaseInfo {
+    pub platform : Platform,
+    pub version : Version,
+    /// the download link, if the release is in the local repo.
+    pub link : Option<String>,
+    /// the folder the release is (or would be) installed in.
+    pub path : PathBuf,
+    pub installed : bool,
 }
\ No newline at end of file
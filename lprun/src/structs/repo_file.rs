@@ -0,0 +1,87 @@
+use structs::release::Release;
+use sources::{self, Validators};
+
+use std::collections::HashSet;
+
+/// The version of the repo file this lprun writes, and the newest it can read.
+pub const SCHEMA_VERSION : i64 = 1;
+
+/// The local repo file, all the known releases and what was last
+/// fetched from each source.
+/// 
+/// files from before the schema was versioned (schema 0) only have `releases`.
+#[derive(Default,Serialize,Deserialize)]
+pub struct RepoFile {
+    #[serde(default)]
+    pub schema : i64,
+    #[serde(default)]
+    pub sources : Vec<SourceInfo>,
+    #[serde(default)]
+    pub releases : Vec<Release>,
+}
+
+impl RepoFile {
+    pub fn new(sources : Vec<SourceInfo>, releases : HashSet<Release>) -> RepoFile {
+        RepoFile {
+            schema : SCHEMA_VERSION,
+            sources,
+            releases : releases.into_iter().collect(),
+        }
+    }
+
+    pub fn source(&self, link : &str) -> Option<&SourceInfo> {
+        self.sources.iter().find(|source| source.link == link)
+    }
+
+    pub fn migrate(&mut self) {
+        //! brings a file from an older schema up to the current one.
+        
+        if self.schema < 1 {
+            // schema 0 didn't know the archive format, but it is always in the link.
+            for release in self.releases.iter_mut() {
+                if release.format.is_none() {
+                    release.format = sources::archive_format(&release.link);
+                }
+            }
+        }
+
+        self.schema = SCHEMA_VERSION;
+    }
+}
+
+/// What was last fetched from a repo link.
+#[derive(Clone,Serialize,Deserialize)]
+pub struct SourceInfo {
+    pub link : String,
+    /// when the source was last fetched, in seconds since the unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_fetched : Option<u64>,
+    #[serde(default)]
+    pub pages : Vec<PageInfo>,
+}
+
+/// A page of a source, what it was served with and the links of the
+/// releases that were on it.
+#[derive(Clone,Serialize,Deserialize)]
+pub struct PageInfo {
+    /// the cursor used to get this page, `None` for the first one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor : Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next : Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag : Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified : Option<String>,
+    #[serde(default)]
+    pub links : Vec<String>,
+}
+
+impl PageInfo {
+    pub fn validators(&self) -> Validators {
+        Validators {
+            etag : self.etag.clone(),
+            last_modified : self.last_modified.clone(),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! helpers for the tests.
+
+use std::env;
+use std::fs::{create_dir_all, remove_dir_all, File};
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::thread::{self, JoinHandle};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static COUNTER : AtomicUsize = AtomicUsize::new(0);
+
+pub fn temp_folder(name : &str) -> PathBuf {
+    //! an empty folder in the temp folder, unique to the test that asked for it.
+    
+    let folder = env::temp_dir().join(format!("lprun-test-{}-{}-{}",
+        process::id(), name, COUNTER.fetch_add(1, Ordering::SeqCst)));
+    if folder.exists() { remove_dir_all(&folder).unwrap(); }
+    create_dir_all(&folder).unwrap();
+    folder
+}
+
+pub fn touch<P : AsRef<Path>>(path : P) {
+    //! creates an empty file, and all the folders it needs.
+    
+    let path = path.as_ref();
+    create_dir_all(path.parent().unwrap()).unwrap();
+    File::create(path).unwrap();
+}
+
+pub fn serve<F>(connections : usize, mut respond : F) -> (String, JoinHandle<Vec<String>>)
+    where F : FnMut(usize, &str) -> Vec<u8> + Send + 'static {
+    //! a stand-in http server on localhost for the next `connections` connections. 
+    //! `respond` gets the number of the connection and the request, and gives back 
+    //! the raw response, which is sent before the connection is closed.
+    //! 
+    //! gives back the server's url, and the requests it got once it is done.
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}",listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+        let mut requests : Vec<String> = Vec::new();
+        for number in 0 .. connections {
+            let (mut stream, _) = listener.accept().unwrap();
+
+            // only the headers are read, none of the tests send a body
+            let mut request : Vec<u8> = Vec::new();
+            let mut buffer = [0u8; 1024];
+            while !String::from_utf8_lossy(&request).contains("\r\n\r\n") {
+                let read = stream.read(&mut buffer).unwrap();
+                if read == 0 { break; }
+                request.extend_from_slice(&buffer[..read]);
+            }
+            let request = String::from_utf8_lossy(&request).to_string();
+
+            let response = respond(number, &request);
+            // the client might have hung up already, which some tests want
+            let _ = stream.write_all(&response);
+            let _ = stream.flush();
+            requests.push(request);
+        }
+        requests
+    });
+
+    (url, handle)
+}
+
+pub fn response(status : &str, headers : &[(&str, String)], body : &[u8]) -> Vec<u8> {
+    //! a raw http response, the `Content-Length` has to be in the `headers`.
+    
+    let mut raw = format!("HTTP/1.1 {}\r\nConnection: close\r\n",status);
+    for &(name, ref value) in headers {
+        raw.push_str(&format!("{}: {}\r\n",name,value));
+    }
+    raw.push_str("\r\n");
+
+    let mut raw = raw.into_bytes();
+    raw.extend_from_slice(body);
+    raw
+}